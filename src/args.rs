@@ -0,0 +1,247 @@
+use bindings;
+
+use std::collections::HashSet;
+use std::ffi::CStr;
+use libc::c_char;
+
+use ffi;
+use super::{Error, Result};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ArgType {
+    Integer,
+    Floating,
+    String,
+    Boolean,
+    StringList,
+}
+
+impl ArgType {
+    fn from_raw(raw: i32) -> Option<Self> {
+        match raw & !bindings::ARG_REQUIRED {
+            bindings::ARG_INTEGER => Some(ArgType::Integer),
+            bindings::ARG_FLOATING => Some(ArgType::Floating),
+            bindings::ARG_STRING => Some(ArgType::String),
+            bindings::ARG_BOOLEAN => Some(ArgType::Boolean),
+            bindings::ARG_STRING_LIST => Some(ArgType::StringList),
+            _ => None,
+        }
+    }
+}
+
+/// Definition of configuration argument from `ps_args()`.
+#[derive(Clone, Copy, Debug)]
+pub struct ArgDef {
+    pub name: &'static str,
+    pub arg_type: ArgType,
+    pub required: bool,
+    pub default: Option<&'static str>,
+    pub doc: Option<&'static str>,
+}
+
+/// Current value of configuration argument.
+#[derive(Clone, Debug, PartialEq)]
+pub enum ArgValue {
+    Integer(i64),
+    Floating(f64),
+    String(Option<String>),
+    Boolean(bool),
+    StringList(Vec<String>),
+}
+
+pub struct ArgDefs {
+    raw: *const ffi::arg_t,
+}
+
+impl Iterator for ArgDefs {
+    type Item = ArgDef;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let arg = unsafe { &*self.raw };
+            if arg.name.is_null() {
+                return None;
+            }
+            self.raw = unsafe { self.raw.offset(1) };
+
+            // Skip entries which can't be represented, there are none in practice.
+            let name = match unsafe { static_str(arg.name) } {
+                Some(name) => name,
+                None => continue,
+            };
+            let arg_type = match ArgType::from_raw(arg.type_) {
+                Some(arg_type) => arg_type,
+                None => continue,
+            };
+            return Some(ArgDef {
+                name,
+                arg_type,
+                required: arg.type_ & bindings::ARG_REQUIRED != 0,
+                default: unsafe { static_str(arg.deflt) },
+                doc: unsafe { static_str(arg.doc) },
+            });
+        }
+    }
+}
+
+unsafe fn static_str(ptr: *const c_char) -> Option<&'static str> {
+    if ptr.is_null() {
+        None
+    } else {
+        CStr::from_ptr(ptr).to_str().ok()
+    }
+}
+
+/// Iterates over all arguments decoder understands.
+pub fn arg_defs() -> ArgDefs {
+    ArgDefs { raw: unsafe { bindings::ps_args() } as *const ffi::arg_t }
+}
+
+// Looks up declared type of argument in `ps_args()` definitions.
+pub(crate) fn arg_type(name: &str) -> Option<ArgType> {
+    arg_defs().find(|def| def.name == name).map(|def| def.arg_type)
+}
+
+pub(crate) fn parse_int(value: &str) -> Option<i64> {
+    value.trim().parse().ok()
+}
+
+pub(crate) fn parse_float(value: &str) -> Option<f64> {
+    value.trim().parse().ok()
+}
+
+// Sphinx only looks at first character of boolean values.
+pub(crate) fn parse_boolean(value: &str) -> Option<bool> {
+    match value.chars().next() {
+        Some('y') | Some('Y') | Some('t') | Some('T') | Some('1') => Some(true),
+        Some('n') | Some('N') | Some('f') | Some('F') | Some('0') => Some(false),
+        _ => None,
+    }
+}
+
+fn is_valid_value(arg_type: ArgType, value: &str) -> bool {
+    match arg_type {
+        ArgType::Integer => parse_int(value).is_some(),
+        ArgType::Floating => parse_float(value).is_some(),
+        ArgType::Boolean => parse_boolean(value).is_some(),
+        ArgType::String | ArgType::StringList => true,
+    }
+}
+
+// Splits arguments file into tokens the same way `cmd_ln_parse_file_r` does:
+// tokens are separated by whitespace and may be quoted, unquoted token
+// starting with '#' comments out rest of line. Returns tokens along with
+// line numbers they start at.
+fn tokenize(contents: &str) -> Vec<(usize, String)> {
+    let mut tokens = Vec::new();
+    let mut current: Option<(usize, String)> = None;
+    let mut quote = None;
+    let mut comment = false;
+    let mut line = 1;
+    for ch in contents.chars() {
+        if comment {
+            comment = ch != '\n';
+        } else if quote == Some(ch) {
+            quote = None;
+        } else if quote.is_none() && (ch == '"' || ch == '\'') {
+            quote = Some(ch);
+            current.get_or_insert_with(|| (line, String::new()));
+        } else if quote.is_none() && ch.is_whitespace() {
+            tokens.extend(current.take());
+        } else if quote.is_none() && ch == '#' && current.is_none() {
+            comment = true;
+        } else {
+            current.get_or_insert_with(|| (line, String::new())).1.push(ch);
+        }
+        if ch == '\n' {
+            line += 1;
+        }
+    }
+    tokens.extend(current.take());
+    tokens
+}
+
+// Validates arguments file contents so that errors point to exact line and
+// argument, Sphinx itself only logs them.
+pub(crate) fn check_args_file(contents: &str, strict: bool) -> Result<()> {
+    let tokens = tokenize(contents);
+    let mut seen = HashSet::new();
+    for pair in tokens.chunks(2) {
+        let (line, ref flag) = pair[0];
+        let error = |message: String| Error::ConfigParse {
+            line: Some(line),
+            flag: Some(flag.clone()),
+            message,
+        };
+        let value = match pair.get(1) {
+            Some((_, value)) => value,
+            None => return Err(error("missing value".to_owned())),
+        };
+        match arg_type(flag) {
+            Some(arg_type) => if !is_valid_value(arg_type, value) {
+                return Err(error(format!("invalid {:?} value '{}'", arg_type, value)));
+            },
+            None => if strict {
+                return Err(error("unknown argument".to_owned()));
+            },
+        }
+        if strict && !seen.insert(flag) {
+            return Err(error("duplicate argument".to_owned()));
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn owned(tokens: &[(usize, &str)]) -> Vec<(usize, String)> {
+        tokens.iter().map(|&(line, token)| (line, token.to_owned())).collect()
+    }
+
+    #[test]
+    fn tokens_are_split_by_whitespace() {
+        assert_eq!(tokenize("-hmm  model\n\t-lw 6.5\n"),
+                   owned(&[(1, "-hmm"), (1, "model"), (2, "-lw"), (2, "6.5")]));
+    }
+
+    #[test]
+    fn quotes_keep_whitespace() {
+        assert_eq!(tokenize("-dict \"my dict.dic\" -lm 'a \"b\"'"),
+                   owned(&[(1, "-dict"), (1, "my dict.dic"), (1, "-lm"), (1, "a \"b\"")]));
+        assert_eq!(tokenize("-logfn \"\""), owned(&[(1, "-logfn"), (1, "")]));
+    }
+
+    #[test]
+    fn comments_are_skipped() {
+        assert_eq!(tokenize("# comment -lw 1\n-lw 2 # trailing\n-dict a#b\n"),
+                   owned(&[(2, "-lw"), (2, "2"), (3, "-dict"), (3, "a#b")]));
+    }
+
+    #[test]
+    fn quoted_token_starts_at_its_line() {
+        assert_eq!(tokenize("-a\n\"multi\nline\" -b 1"),
+                   owned(&[(1, "-a"), (2, "multi\nline"), (3, "-b"), (3, "1")]));
+    }
+
+    #[test]
+    fn missing_value_error_points_to_line() {
+        match check_args_file("\n\n-hmm", false) {
+            Err(Error::ConfigParse { line, flag, .. }) => {
+                assert_eq!(line, Some(3));
+                assert_eq!(flag.as_deref(), Some("-hmm"));
+            }
+            other => panic!("unexpected result {:?}", other),
+        }
+    }
+
+    #[test]
+    fn values_are_parsed_like_sphinx() {
+        assert_eq!(parse_boolean("yes"), Some(true));
+        assert_eq!(parse_boolean("FALSE"), Some(false));
+        assert_eq!(parse_boolean("maybe"), None);
+        assert_eq!(parse_int(" 42 "), Some(42));
+        assert_eq!(parse_float("1e-3"), Some(0.001));
+    }
+}
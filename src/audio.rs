@@ -0,0 +1,148 @@
+use super::{Error, PsDecoder, Result, Utterance};
+
+/// Block of audio samples in one of supported formats.
+///
+/// Multichannel audio is interleaved, channels are averaged before decoding.
+#[derive(Clone, Copy, Debug)]
+pub enum AudioFrame<'a> {
+    I16 { samples: &'a [i16], channels: u16 },
+    /// Samples in [-1, 1] range, values outside of it are clipped.
+    F32 { samples: &'a [f32], channels: u16 },
+    /// G.711 µ-law.
+    MuLaw { samples: &'a [u8], channels: u16 },
+    /// G.711 A-law.
+    ALaw { samples: &'a [u8], channels: u16 },
+}
+
+impl<'a> AudioFrame<'a> {
+    pub fn channels(&self) -> u16 {
+        match *self {
+            AudioFrame::I16 { channels, .. } |
+            AudioFrame::F32 { channels, .. } |
+            AudioFrame::MuLaw { channels, .. } |
+            AudioFrame::ALaw { channels, .. } => channels,
+        }
+    }
+
+    fn len(&self) -> usize {
+        match *self {
+            AudioFrame::I16 { samples, .. } => samples.len(),
+            AudioFrame::F32 { samples, .. } => samples.len(),
+            AudioFrame::MuLaw { samples, .. } | AudioFrame::ALaw { samples, .. } => samples.len(),
+        }
+    }
+
+    /// Converts samples into mono 16-bit PCM expected by decoder.
+    pub fn to_mono_i16(&self) -> Result<Vec<i16>> {
+        let channels = self.channels() as usize;
+        if channels == 0 {
+            return Err(Error::Audio("audio must have at least one channel".to_owned()));
+        }
+        if !self.len().is_multiple_of(channels) {
+            return Err(Error::Audio(format!("{} samples can't be split into {} channels",
+                                            self.len(), channels)));
+        }
+        Ok(match *self {
+            AudioFrame::I16 { samples, .. } if channels == 1 => samples.to_vec(),
+            AudioFrame::I16 { samples, .. } => downmix(samples.iter().cloned(), channels),
+            AudioFrame::F32 { samples, .. } => downmix(samples.iter().map(|&s| f32_to_i16(s)), channels),
+            AudioFrame::MuLaw { samples, .. } => downmix(samples.iter().map(|&s| mulaw_to_i16(s)), channels),
+            AudioFrame::ALaw { samples, .. } => downmix(samples.iter().map(|&s| alaw_to_i16(s)), channels),
+        })
+    }
+}
+
+fn downmix<I: Iterator<Item = i16>>(mut samples: I, channels: usize) -> Vec<i16> {
+    let mut mono = Vec::with_capacity(samples.size_hint().0 / channels);
+    loop {
+        let mut sum = 0i32;
+        for _ in 0..channels {
+            match samples.next() {
+                Some(sample) => sum += sample as i32,
+                None => return mono,
+            }
+        }
+        mono.push((sum / channels as i32) as i16);
+    }
+}
+
+pub(crate) fn f32_to_i16(sample: f32) -> i16 {
+    (sample.clamp(-1.0, 1.0) * i16::MAX as f32) as i16
+}
+
+pub(crate) fn mulaw_to_i16(sample: u8) -> i16 {
+    let sample = !sample;
+    let exponent = (sample >> 4) & 0x07;
+    let mantissa = (sample & 0x0f) as i32;
+    let magnitude = (((mantissa << 3) + 0x84) << exponent) - 0x84;
+    (if sample & 0x80 != 0 { -magnitude } else { magnitude }) as i16
+}
+
+pub(crate) fn alaw_to_i16(sample: u8) -> i16 {
+    let sample = sample ^ 0x55;
+    let exponent = (sample >> 4) & 0x07;
+    let mantissa = (sample & 0x0f) as i32;
+    let magnitude = if exponent == 0 {
+        (mantissa << 4) + 8
+    } else {
+        ((mantissa << 4) + 0x108) << (exponent - 1)
+    };
+    (if sample & 0x80 != 0 { magnitude } else { -magnitude }) as i16
+}
+
+impl PsDecoder {
+    pub fn process_samples(&self, frame: AudioFrame, no_search: bool, full_utt: bool) -> Result<i32> {
+        self.process_raw(&frame.to_mono_i16()?, no_search, full_utt)
+    }
+}
+
+impl<'a> Utterance<'a> {
+    pub fn process_samples(&mut self, frame: AudioFrame) -> Result<i32> {
+        self.process(&frame.to_mono_i16()?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn mulaw_matches_g711_reference() {
+        let reference = [
+            (0x00, -32124), (0x0f, -16764), (0x10, -15996), (0x70, -120), (0x7e, -8), (0x7f, 0),
+            (0x80, 32124), (0x8f, 16764), (0x90, 15996), (0xf0, 120), (0xfe, 8), (0xff, 0),
+        ];
+        for &(code, linear) in &reference {
+            assert_eq!(mulaw_to_i16(code), linear, "code {:#04x}", code);
+        }
+    }
+
+    #[test]
+    fn alaw_matches_g711_reference() {
+        let reference = [
+            (0xd5, 8), (0x55, -8), (0xd4, 24), (0xaa, 32256), (0x2a, -32256),
+            (0x80, 5504), (0x00, -5504), (0xc5, 264), (0xe5, 1056),
+        ];
+        for &(code, linear) in &reference {
+            assert_eq!(alaw_to_i16(code), linear, "code {:#04x}", code);
+        }
+    }
+
+    #[test]
+    fn float_is_scaled_and_clipped() {
+        let frame = AudioFrame::F32 { samples: &[0.0, 0.5, -1.0, 2.0, -2.0], channels: 1 };
+        assert_eq!(frame.to_mono_i16().unwrap(), vec![0, 16383, -32767, 32767, -32767]);
+    }
+
+    #[test]
+    fn channels_are_averaged() {
+        let frame = AudioFrame::I16 { samples: &[100, 300, -100, -200, 32767, 32767], channels: 2 };
+        assert_eq!(frame.to_mono_i16().unwrap(), vec![200, -150, 32767]);
+    }
+
+    #[test]
+    fn incomplete_frames_are_rejected() {
+        assert!(AudioFrame::I16 { samples: &[1, 2, 3], channels: 2 }.to_mono_i16().is_err());
+        assert!(AudioFrame::I16 { samples: &[1, 2], channels: 0 }.to_mono_i16().is_err());
+    }
+}
@@ -0,0 +1,140 @@
+use std::ffi::{CString, OsStr, OsString};
+use std::path::Path;
+
+use std::os::unix::ffi::OsStrExt;
+
+use super::{CmdLn, Result};
+
+/// Typed builder for decoder configuration.
+///
+/// Covers the common `ps_args()` options, use `arg` for anything else.
+#[derive(Clone, Debug)]
+pub struct Config {
+    args: Vec<(String, OsString)>,
+    strict: bool,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Config::new()
+    }
+}
+
+impl Config {
+    pub fn new() -> Self {
+        Config { args: Vec::new(), strict: true }
+    }
+
+    /// Whether unknown arguments are rejected when building `CmdLn`, `true` by default.
+    pub fn strict(mut self, strict: bool) -> Self {
+        self.strict = strict;
+        self
+    }
+
+    /// Sets raw argument value, leading dash in `name` is optional.
+    pub fn arg<V: AsRef<OsStr>>(mut self, name: &str, value: V) -> Self {
+        let name = if name.starts_with('-') { name.to_owned() } else { format!("-{}", name) };
+        let value = value.as_ref().to_owned();
+        match self.args.iter().position(|(n, _)| *n == name) {
+            Some(pos) => self.args[pos].1 = value,
+            None => self.args.push((name, value)),
+        }
+        self
+    }
+
+    pub fn get(&self, name: &str) -> Option<&OsStr> {
+        let name = name.trim_start_matches('-');
+        self.args.iter()
+            .find(|(n, _)| &n[1..] == name)
+            .map(|(_, v)| v.as_os_str())
+    }
+
+    pub fn args<'a>(&'a self) -> impl Iterator<Item = (&'a str, &'a OsStr)> + 'a {
+        self.args.iter().map(|(n, v)| (n.as_str(), v.as_os_str()))
+    }
+
+    pub fn to_cmdln(&self) -> Result<CmdLn> {
+        let mut c_args = vec![CString::new("pocketsphinx")?];
+        for (name, value) in &self.args {
+            c_args.push(CString::new(name.as_str())?);
+            c_args.push(CString::new(value.as_bytes())?);
+        }
+        CmdLn::parse_c_args(self.strict, &c_args)
+    }
+
+    fn path<P: AsRef<Path>>(self, name: &str, path: P) -> Self {
+        self.arg(name, path.as_ref().as_os_str())
+    }
+
+    fn string(self, name: &str, value: &str) -> Self {
+        self.arg(name, value)
+    }
+
+    fn int(self, name: &str, value: i32) -> Self {
+        self.arg(name, value.to_string())
+    }
+
+    fn float(self, name: &str, value: f64) -> Self {
+        self.arg(name, value.to_string())
+    }
+
+    fn boolean(self, name: &str, value: bool) -> Self {
+        self.arg(name, if value { "yes" } else { "no" })
+    }
+
+    // Models and searches.
+
+    pub fn hmm<P: AsRef<Path>>(self, path: P) -> Self { self.path("-hmm", path) }
+    pub fn lm<P: AsRef<Path>>(self, path: P) -> Self { self.path("-lm", path) }
+    pub fn lmctl<P: AsRef<Path>>(self, path: P) -> Self { self.path("-lmctl", path) }
+    pub fn lmname(self, name: &str) -> Self { self.string("-lmname", name) }
+    pub fn dict<P: AsRef<Path>>(self, path: P) -> Self { self.path("-dict", path) }
+    pub fn fdict<P: AsRef<Path>>(self, path: P) -> Self { self.path("-fdict", path) }
+    pub fn jsgf<P: AsRef<Path>>(self, path: P) -> Self { self.path("-jsgf", path) }
+    pub fn toprule(self, name: &str) -> Self { self.string("-toprule", name) }
+    pub fn fsg<P: AsRef<Path>>(self, path: P) -> Self { self.path("-fsg", path) }
+    pub fn allphone<P: AsRef<Path>>(self, path: P) -> Self { self.path("-allphone", path) }
+    pub fn kws<P: AsRef<Path>>(self, path: P) -> Self { self.path("-kws", path) }
+    pub fn keyphrase(self, keyphrase: &str) -> Self { self.string("-keyphrase", keyphrase) }
+    pub fn kws_threshold(self, threshold: f64) -> Self { self.float("-kws_threshold", threshold) }
+    pub fn kws_delay(self, frames: i32) -> Self { self.int("-kws_delay", frames) }
+    pub fn kws_plp(self, penalty: f64) -> Self { self.float("-kws_plp", penalty) }
+    pub fn mllr<P: AsRef<Path>>(self, path: P) -> Self { self.path("-mllr", path) }
+
+    // Front end.
+
+    pub fn samprate(self, rate: i32) -> Self { self.float("-samprate", rate as f64) }
+    pub fn frate(self, rate: i32) -> Self { self.int("-frate", rate) }
+    pub fn nfft(self, size: i32) -> Self { self.int("-nfft", size) }
+    pub fn dither(self, dither: bool) -> Self { self.boolean("-dither", dither) }
+    pub fn remove_noise(self, remove: bool) -> Self { self.boolean("-remove_noise", remove) }
+    pub fn remove_silence(self, remove: bool) -> Self { self.boolean("-remove_silence", remove) }
+    pub fn vad_threshold(self, threshold: f64) -> Self { self.float("-vad_threshold", threshold) }
+    pub fn vad_prespeech(self, frames: i32) -> Self { self.int("-vad_prespeech", frames) }
+    pub fn vad_postspeech(self, frames: i32) -> Self { self.int("-vad_postspeech", frames) }
+
+    // Search tuning.
+
+    pub fn logbase(self, base: f64) -> Self { self.float("-logbase", base) }
+    pub fn beam(self, beam: f64) -> Self { self.float("-beam", beam) }
+    pub fn wbeam(self, beam: f64) -> Self { self.float("-wbeam", beam) }
+    pub fn pbeam(self, beam: f64) -> Self { self.float("-pbeam", beam) }
+    pub fn lw(self, weight: f64) -> Self { self.float("-lw", weight) }
+    pub fn wip(self, penalty: f64) -> Self { self.float("-wip", penalty) }
+    pub fn pip(self, penalty: f64) -> Self { self.float("-pip", penalty) }
+    pub fn silprob(self, prob: f64) -> Self { self.float("-silprob", prob) }
+    pub fn fillprob(self, prob: f64) -> Self { self.float("-fillprob", prob) }
+    pub fn maxwpf(self, max: i32) -> Self { self.int("-maxwpf", max) }
+    pub fn maxhmmpf(self, max: i32) -> Self { self.int("-maxhmmpf", max) }
+    pub fn pl_window(self, frames: i32) -> Self { self.int("-pl_window", frames) }
+    pub fn topn(self, n: i32) -> Self { self.int("-topn", n) }
+    pub fn fwdtree(self, enable: bool) -> Self { self.boolean("-fwdtree", enable) }
+    pub fn fwdflat(self, enable: bool) -> Self { self.boolean("-fwdflat", enable) }
+    pub fn bestpath(self, enable: bool) -> Self { self.boolean("-bestpath", enable) }
+    pub fn ascale(self, scale: f64) -> Self { self.float("-ascale", scale) }
+    pub fn backtrace(self, enable: bool) -> Self { self.boolean("-backtrace", enable) }
+
+    // Logging.
+
+    pub fn logfn<P: AsRef<Path>>(self, path: P) -> Self { self.path("-logfn", path) }
+}
@@ -0,0 +1,213 @@
+use std::collections::VecDeque;
+use std::time::Duration;
+
+use super::{Hypothesis, PsDecoder, Resampler, Result};
+
+/// Always-on listener which splits endless audio into utterances, like
+/// `continuous.c` from PocketSphinx.
+///
+/// Utterance starts when speech is detected and ends after `hangover` of
+/// silence following it, silence is detected by decoder itself according to
+/// `-vad_*` arguments. Utterances without recognized words are skipped.
+pub struct ContinuousDecoder<'a> {
+    decoder: &'a mut PsDecoder,
+    hangover: Duration,
+    max_utterance: Option<Duration>,
+    resampler: Option<Resampler>,
+    started: bool,
+    in_speech: bool,
+    speech_samples: u64,
+    silence_samples: u64,
+    // Utterances ended while flushing resampler in `set_input_rate`, they
+    // are reported by next `process` or `flush`.
+    ended: Vec<Hypothesis>,
+}
+
+impl PsDecoder {
+    pub fn continuous(&mut self) -> ContinuousDecoder<'_> {
+        ContinuousDecoder {
+            decoder: self,
+            hangover: Duration::from_millis(0),
+            max_utterance: None,
+            resampler: None,
+            started: false,
+            in_speech: false,
+            speech_samples: 0,
+            silence_samples: 0,
+            ended: Vec::new(),
+        }
+    }
+}
+
+fn duration_samples(duration: Duration, rate: u32) -> u64 {
+    (duration.as_secs_f64() * rate as f64) as u64
+}
+
+impl<'a> ContinuousDecoder<'a> {
+    /// Sets silence which must follow speech to end utterance in addition to
+    /// `-vad_postspeech`, zero by default.
+    pub fn set_hangover(&mut self, hangover: Duration) {
+        self.hangover = hangover;
+    }
+
+    /// Sets length of speech after which utterance is ended even if speaker
+    /// didn't pause, unlimited by default.
+    pub fn set_max_utterance(&mut self, max_utterance: Option<Duration>) {
+        self.max_utterance = max_utterance;
+    }
+
+    /// Declares sample rate of audio, it is converted to decoder's
+    /// `-samprate` when they differ.
+    pub fn set_input_rate(&mut self, rate: u32) -> Result<()> {
+        let mut rest = Vec::new();
+        if let Some(ref mut resampler) = self.resampler {
+            resampler.flush(&mut rest);
+        }
+        let mut ended = std::mem::take(&mut self.ended);
+        self.process_resampled(&rest, &mut |hyp| ended.push(hyp))?;
+        self.ended = ended;
+
+        let model_rate = self.decoder.sample_rate();
+        self.resampler = if rate == model_rate {
+            None
+        } else {
+            Some(Resampler::new(rate, model_rate)?)
+        };
+        Ok(())
+    }
+
+    /// Processes next block of audio, calls `on_hyp` for each utterance
+    /// ended within it.
+    pub fn process<F: FnMut(Hypothesis)>(&mut self, data: &[i16], mut on_hyp: F) -> Result<()> {
+        self.ended.drain(..).for_each(&mut on_hyp);
+        match self.resampler {
+            Some(ref mut resampler) => {
+                let mut resampled = Vec::with_capacity(data.len());
+                resampler.process(data, &mut resampled);
+                self.process_resampled(&resampled, &mut on_hyp)
+            }
+            None => self.process_resampled(data, &mut on_hyp),
+        }
+    }
+
+    /// Ends current utterance, e.g. when audio stream is over.
+    pub fn flush<F: FnMut(Hypothesis)>(&mut self, mut on_hyp: F) -> Result<()> {
+        self.ended.drain(..).for_each(&mut on_hyp);
+        let mut rest = Vec::new();
+        if let Some(ref mut resampler) = self.resampler {
+            resampler.flush(&mut rest);
+        }
+        self.process_resampled(&rest, &mut on_hyp)?;
+        if self.started {
+            if let Some(hyp) = self.end_utterance()? {
+                on_hyp(hyp);
+            }
+        }
+        Ok(())
+    }
+
+    /// Turns audio blocks into iterator over recognized utterances, the
+    /// last one is flushed when audio is over.
+    pub fn hypotheses<I: IntoIterator<Item = Vec<i16>>>(self, audio: I) -> Hypotheses<'a, I::IntoIter> {
+        Hypotheses {
+            decoder: self,
+            audio: audio.into_iter(),
+            pending: VecDeque::new(),
+            done: false,
+        }
+    }
+
+    fn process_resampled(&mut self, data: &[i16], on_hyp: &mut dyn FnMut(Hypothesis)) -> Result<()> {
+        // Speech state is checked after each block, 10 ms is precise enough.
+        let block = (self.decoder.sample_rate() as usize / 100).max(1);
+        for chunk in data.chunks(block) {
+            if let Some(hyp) = self.process_block(chunk)? {
+                on_hyp(hyp);
+            }
+        }
+        Ok(())
+    }
+
+    fn process_block(&mut self, data: &[i16]) -> Result<Option<Hypothesis>> {
+        if !self.started {
+            self.decoder.start_utt(None)?;
+            self.started = true;
+        }
+        self.decoder.process_raw(data, false, false)?;
+
+        let samples = data.len() as u64;
+        if self.decoder.get_in_speech() {
+            self.in_speech = true;
+            self.silence_samples = 0;
+        } else if self.in_speech {
+            self.silence_samples += samples;
+        }
+        if !self.in_speech {
+            return Ok(None);
+        }
+        self.speech_samples += samples;
+
+        let rate = self.decoder.sample_rate();
+        let paused = self.silence_samples > 0 &&
+            self.silence_samples >= duration_samples(self.hangover, rate);
+        let too_long = self.max_utterance
+            .is_some_and(|max| self.speech_samples >= duration_samples(max, rate));
+        if paused || too_long {
+            self.end_utterance()
+        } else {
+            Ok(None)
+        }
+    }
+
+    fn end_utterance(&mut self) -> Result<Option<Hypothesis>> {
+        self.started = false;
+        self.in_speech = false;
+        self.speech_samples = 0;
+        self.silence_samples = 0;
+        self.decoder.end_utt()?;
+        Ok(self.decoder.get_hyp().filter(|hyp| !hyp.text.is_empty()))
+    }
+}
+
+impl<'a> Drop for ContinuousDecoder<'a> {
+    fn drop(&mut self) {
+        if self.started {
+            let _ = self.decoder.end_utt();
+        }
+    }
+}
+
+/// Iterator over utterances recognized in audio stream.
+pub struct Hypotheses<'a, I> {
+    decoder: ContinuousDecoder<'a>,
+    audio: I,
+    pending: VecDeque<Hypothesis>,
+    done: bool,
+}
+
+impl<'a, I: Iterator<Item = Vec<i16>>> Iterator for Hypotheses<'a, I> {
+    type Item = Result<Hypothesis>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(hyp) = self.pending.pop_front() {
+                return Some(Ok(hyp));
+            }
+            if self.done {
+                return None;
+            }
+            let pending = &mut self.pending;
+            let result = match self.audio.next() {
+                Some(block) => self.decoder.process(&block, |hyp| pending.push_back(hyp)),
+                None => {
+                    self.done = true;
+                    self.decoder.flush(|hyp| pending.push_back(hyp))
+                }
+            };
+            if let Err(err) = result {
+                self.done = true;
+                return Some(Err(err));
+            }
+        }
+    }
+}
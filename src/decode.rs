@@ -0,0 +1,317 @@
+// Decoding of whole files and streams: RIFF/WAV or headerless PCM.
+
+use std::fs::File;
+use std::io::{self, BufReader, Read};
+use std::path::Path;
+
+use super::{AudioFrame, Error, Hypothesis, PsDecoder, Result, Utterance};
+
+const WAVE_FORMAT_PCM: u16 = 1;
+const WAVE_FORMAT_IEEE_FLOAT: u16 = 3;
+const WAVE_FORMAT_ALAW: u16 = 6;
+const WAVE_FORMAT_MULAW: u16 = 7;
+const WAVE_FORMAT_EXTENSIBLE: u16 = 0xfffe;
+// Size of `WAVEFORMATEXTENSIBLE`, the longest known `fmt ` chunk.
+const MAX_FMT_SIZE: usize = 40;
+
+// Number of bytes read from stream at once.
+const BLOCK_SIZE: usize = 8192;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SampleFormat {
+    /// Signed 16-bit little-endian.
+    I16,
+    /// 32-bit little-endian float in [-1, 1] range.
+    F32,
+    /// G.711 µ-law.
+    MuLaw,
+    /// G.711 A-law.
+    ALaw,
+}
+
+impl SampleFormat {
+    fn size(self) -> usize {
+        match self {
+            SampleFormat::I16 => 2,
+            SampleFormat::F32 => 4,
+            SampleFormat::MuLaw | SampleFormat::ALaw => 1,
+        }
+    }
+}
+
+/// Format of audio stream passed to `decode_reader`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AudioFormat {
+    /// RIFF/WAV, sample format, rate and channels are read from header.
+    Wav,
+    /// Headerless interleaved samples.
+    Raw { sample_format: SampleFormat, sample_rate: u32, channels: u16 },
+}
+
+impl PsDecoder {
+    /// Decodes WAV file, use `decode_reader` for raw samples.
+    pub fn decode_file<P: AsRef<Path>>(&mut self, path: P) -> Result<Option<Hypothesis>> {
+        self.decode_reader(BufReader::new(File::open(path)?), AudioFormat::Wav)
+    }
+
+    /// Decodes whole stream as single utterance.
+    ///
+    /// Audio in sample rate other than `-samprate` is resampled.
+    pub fn decode_reader<R: Read>(&mut self, mut reader: R, format: AudioFormat)
+                                  -> Result<Option<Hypothesis>> {
+        let (sample_format, sample_rate, channels, data_size) = match format {
+            AudioFormat::Wav => read_wav_header(&mut reader)?,
+            AudioFormat::Raw { sample_format, sample_rate, channels } =>
+                (sample_format, sample_rate, channels, None),
+        };
+        if channels == 0 {
+            return Err(Error::Audio("audio must have at least one channel".to_owned()));
+        }
+
+        let mut utterance = self.utterance(None)?;
+        utterance.set_input_rate(sample_rate)?;
+        let mut reader = reader.take(data_size.unwrap_or(u64::MAX));
+        let frame_size = sample_format.size() * channels as usize;
+        // Buffer holds whole frames and at least one of them.
+        let block_size = BLOCK_SIZE.max(frame_size);
+        let mut buffer = vec![0; block_size - block_size % frame_size];
+        let mut filled = 0;
+        loop {
+            let read = match reader.read(&mut buffer[filled..]) {
+                Ok(read) => read,
+                Err(ref err) if err.kind() == io::ErrorKind::Interrupted => continue,
+                Err(err) => return Err(err.into()),
+            };
+            // Incomplete sample at the end of stream is ignored.
+            if read == 0 {
+                break;
+            }
+            filled += read;
+            let usable = filled - filled % frame_size;
+            process_bytes(&mut utterance, sample_format, channels, &buffer[..usable])?;
+            buffer.copy_within(usable..filled, 0);
+            filled -= usable;
+        }
+        utterance.finish()
+    }
+}
+
+fn process_bytes(utterance: &mut Utterance, sample_format: SampleFormat, channels: u16,
+                 bytes: &[u8]) -> Result<i32> {
+    match sample_format {
+        SampleFormat::I16 => {
+            let samples: Vec<i16> = bytes.chunks(2)
+                .map(|b| i16::from_le_bytes([b[0], b[1]]))
+                .collect();
+            utterance.process_samples(AudioFrame::I16 { samples: &samples, channels })
+        }
+        SampleFormat::F32 => {
+            let samples: Vec<f32> = bytes.chunks(4)
+                .map(|b| f32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+                .collect();
+            utterance.process_samples(AudioFrame::F32 { samples: &samples, channels })
+        }
+        SampleFormat::MuLaw => utterance.process_samples(AudioFrame::MuLaw { samples: bytes, channels }),
+        SampleFormat::ALaw => utterance.process_samples(AudioFrame::ALaw { samples: bytes, channels }),
+    }
+}
+
+fn read_u16(bytes: &[u8]) -> u16 {
+    u16::from_le_bytes([bytes[0], bytes[1]])
+}
+
+fn read_u32(bytes: &[u8]) -> u32 {
+    u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]])
+}
+
+fn invalid_wav(message: &str) -> Error {
+    Error::Audio(format!("invalid WAV: {}", message))
+}
+
+// Reads headers up to beginning of samples, returns sample format, rate,
+// channels and size of data if it is known.
+fn read_wav_header<R: Read>(reader: &mut R) -> Result<(SampleFormat, u32, u16, Option<u64>)> {
+    let mut riff = [0; 12];
+    match reader.read_exact(&mut riff) {
+        Ok(()) => {}
+        Err(ref err) if err.kind() == io::ErrorKind::UnexpectedEof =>
+            return Err(invalid_wav("no RIFF/WAVE header")),
+        Err(err) => return Err(err.into()),
+    }
+    if &riff[0..4] != b"RIFF" || &riff[8..12] != b"WAVE" {
+        return Err(invalid_wav("no RIFF/WAVE header"));
+    }
+
+    let mut format = None;
+    loop {
+        let mut header = [0; 8];
+        match reader.read_exact(&mut header) {
+            Ok(()) => {}
+            Err(ref err) if err.kind() == io::ErrorKind::UnexpectedEof =>
+                return Err(invalid_wav("no data chunk")),
+            Err(err) => return Err(err.into()),
+        }
+        let size = read_u32(&header[4..8]);
+        match &header[0..4] {
+            b"fmt " => {
+                if size < 16 {
+                    return Err(invalid_wav("fmt chunk is too short"));
+                }
+                // Size comes from file, so only known fields are read.
+                let mut fmt = [0; MAX_FMT_SIZE];
+                let known = (size as usize).min(MAX_FMT_SIZE);
+                reader.read_exact(&mut fmt[..known])?;
+                skip_chunk(reader, size as u64 - known as u64 + size as u64 % 2)?;
+                format = Some(parse_fmt(&fmt[..known])?);
+            }
+            b"data" => {
+                let (sample_format, sample_rate, channels) = format
+                    .ok_or_else(|| invalid_wav("data chunk goes before fmt chunk"))?;
+                // Streaming writers don't know size in advance and put 0 or maximum there.
+                let data_size = match size {
+                    0 | 0xffff_ffff => None,
+                    size => Some(size as u64),
+                };
+                return Ok((sample_format, sample_rate, channels, data_size));
+            }
+            _ => {
+                // Chunks are padded to even size.
+                skip_chunk(reader, size as u64 + size as u64 % 2)?;
+            }
+        }
+    }
+}
+
+fn skip_chunk<R: Read>(reader: &mut R, size: u64) -> Result<()> {
+    let skipped = io::copy(&mut reader.take(size), &mut io::sink())?;
+    if skipped < size {
+        return Err(invalid_wav("truncated chunk"));
+    }
+    Ok(())
+}
+
+fn parse_fmt(fmt: &[u8]) -> Result<(SampleFormat, u32, u16)> {
+    let mut tag = read_u16(&fmt[0..2]);
+    let channels = read_u16(&fmt[2..4]);
+    let sample_rate = read_u32(&fmt[4..8]);
+    let bits = read_u16(&fmt[14..16]);
+    if tag == WAVE_FORMAT_EXTENSIBLE {
+        // Subformat GUID starts with format tag.
+        if fmt.len() < 26 {
+            return Err(invalid_wav("extensible fmt chunk is too short"));
+        }
+        tag = read_u16(&fmt[24..26]);
+    }
+    let sample_format = match (tag, bits) {
+        (WAVE_FORMAT_PCM, 16) => SampleFormat::I16,
+        (WAVE_FORMAT_IEEE_FLOAT, 32) => SampleFormat::F32,
+        (WAVE_FORMAT_MULAW, 8) => SampleFormat::MuLaw,
+        (WAVE_FORMAT_ALAW, 8) => SampleFormat::ALaw,
+        _ => return Err(Error::Audio(format!(
+            "unsupported WAV format {} with {} bits per sample", tag, bits))),
+    };
+    Ok((sample_format, sample_rate, channels))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn chunk(id: &[u8], size: u32, body: &[u8]) -> Vec<u8> {
+        let mut chunk = id.to_vec();
+        chunk.extend_from_slice(&size.to_le_bytes());
+        chunk.extend_from_slice(body);
+        chunk
+    }
+
+    fn fmt_body(tag: u16, channels: u16, rate: u32, bits: u16) -> Vec<u8> {
+        let block_align = channels * bits / 8;
+        let mut body = Vec::new();
+        body.extend_from_slice(&tag.to_le_bytes());
+        body.extend_from_slice(&channels.to_le_bytes());
+        body.extend_from_slice(&rate.to_le_bytes());
+        body.extend_from_slice(&(rate * block_align as u32).to_le_bytes());
+        body.extend_from_slice(&block_align.to_le_bytes());
+        body.extend_from_slice(&bits.to_le_bytes());
+        body
+    }
+
+    fn wav(chunks: &[Vec<u8>]) -> Vec<u8> {
+        let body: Vec<u8> = chunks.concat();
+        let mut wav = b"RIFF".to_vec();
+        wav.extend_from_slice(&(body.len() as u32 + 4).to_le_bytes());
+        wav.extend_from_slice(b"WAVE");
+        wav.extend(body);
+        wav
+    }
+
+    fn header(bytes: &[u8]) -> Result<(SampleFormat, u32, u16, Option<u64>)> {
+        read_wav_header(&mut &bytes[..])
+    }
+
+    #[test]
+    fn pcm_header() {
+        let fmt = fmt_body(WAVE_FORMAT_PCM, 1, 16000, 16);
+        let bytes = wav(&[chunk(b"fmt ", 16, &fmt), chunk(b"data", 4, &[0; 4])]);
+        assert_eq!(header(&bytes).unwrap(), (SampleFormat::I16, 16000, 1, Some(4)));
+    }
+
+    #[test]
+    fn extensible_header() {
+        let mut fmt = fmt_body(WAVE_FORMAT_EXTENSIBLE, 2, 48000, 32);
+        fmt.extend_from_slice(&22u16.to_le_bytes());
+        fmt.extend_from_slice(&32u16.to_le_bytes());
+        fmt.extend_from_slice(&3u32.to_le_bytes());
+        // Subformat GUID, only first two bytes matter.
+        fmt.extend_from_slice(&WAVE_FORMAT_IEEE_FLOAT.to_le_bytes());
+        fmt.extend_from_slice(&[0; 14]);
+        let bytes = wav(&[chunk(b"fmt ", fmt.len() as u32, &fmt), chunk(b"data", 8, &[0; 8])]);
+        assert_eq!(header(&bytes).unwrap(), (SampleFormat::F32, 48000, 2, Some(8)));
+    }
+
+    #[test]
+    fn odd_sized_chunk_is_padded() {
+        let fmt = fmt_body(WAVE_FORMAT_MULAW, 1, 8000, 8);
+        let bytes = wav(&[
+            chunk(b"LIST", 3, &[1, 2, 3, 0]),
+            chunk(b"fmt ", 16, &fmt),
+            chunk(b"data", 2, &[0; 2]),
+        ]);
+        assert_eq!(header(&bytes).unwrap(), (SampleFormat::MuLaw, 8000, 1, Some(2)));
+    }
+
+    #[test]
+    fn long_fmt_chunk_is_skipped() {
+        let mut fmt = fmt_body(WAVE_FORMAT_ALAW, 1, 8000, 8);
+        fmt.extend_from_slice(&[0; 100]);
+        let bytes = wav(&[chunk(b"fmt ", fmt.len() as u32, &fmt), chunk(b"data", 2, &[0; 2])]);
+        assert_eq!(header(&bytes).unwrap(), (SampleFormat::ALaw, 8000, 1, Some(2)));
+    }
+
+    #[test]
+    fn streaming_data_size_is_unknown() {
+        let fmt = fmt_body(WAVE_FORMAT_PCM, 1, 16000, 16);
+        for &size in &[0, 0xffff_ffff] {
+            let bytes = wav(&[chunk(b"fmt ", 16, &fmt), chunk(b"data", size, &[0; 4])]);
+            assert_eq!(header(&bytes).unwrap().3, None);
+        }
+    }
+
+    #[test]
+    fn huge_fmt_chunk_is_not_allocated() {
+        let fmt = fmt_body(WAVE_FORMAT_PCM, 1, 16000, 16);
+        let bytes = wav(&[chunk(b"fmt ", 0xffff_ffff, &fmt)]);
+        assert!(header(&bytes).is_err());
+    }
+
+    #[test]
+    fn invalid_headers_are_rejected() {
+        let fmt = fmt_body(WAVE_FORMAT_PCM, 1, 16000, 16);
+        assert!(header(b"RIFX\0\0\0\0WAVE").is_err());
+        assert!(header(b"ID3").is_err());
+        assert!(header(&wav(&[chunk(b"data", 4, &[0; 4])])).is_err());
+        assert!(header(&wav(&[chunk(b"fmt ", 16, &fmt)])).is_err());
+        let fmt = fmt_body(WAVE_FORMAT_PCM, 1, 16000, 24);
+        assert!(header(&wav(&[chunk(b"fmt ", 16, &fmt), chunk(b"data", 3, &[0; 3])])).is_err());
+    }
+}
@@ -1,22 +1,92 @@
 use std;
+use std::ffi::NulError;
+use std::io;
 
-pub struct Error;
+use logging;
 
-impl std::fmt::Debug for Error {
-    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
-        write!(f, "PocketSphinx error, see log for details")
-    }
+/// Error with reason of failure.
+///
+/// Native failures carry last error message logged by PocketSphinx. Log is
+/// global, so with several decoders used concurrently message may belong to
+/// another decoder.
+#[derive(Debug)]
+pub enum Error {
+    /// Invalid configuration argument, `line` is set when it comes from file.
+    ConfigParse { line: Option<usize>, flag: Option<String>, message: String },
+    DecoderInit { message: String },
+    Search { name: String, message: String },
+    Grammar { line: Option<usize>, message: String },
+    Utterance { message: String },
+    /// Invalid lattice file.
+    Lattice { line: Option<usize>, message: String },
+    /// Audio can't be decoded, its format is invalid or unsupported.
+    Audio(String),
+    Io(io::Error),
+    /// String passed to PocketSphinx contains NUL byte or string got from it is not UTF-8.
+    InvalidString(String),
 }
 
 impl std::error::Error for Error {
-    fn description(&self) -> &str { "PocketSphinx error"}
-    fn cause(&self) -> Option<&std::error::Error> { None }
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match *self {
+            Error::Io(ref err) => Some(err),
+            _ => None,
+        }
+    }
 }
 
 impl std::fmt::Display for Error {
     fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
-        write!(f, "PocketSphinx error, see log for details")
+        match *self {
+            Error::ConfigParse { ref line, ref flag, ref message } => {
+                write!(f, "Invalid configuration")?;
+                if let Some(line) = *line {
+                    write!(f, " at line {}", line)?;
+                }
+                if let Some(ref flag) = *flag {
+                    write!(f, " for argument {}", flag)?;
+                }
+                write!(f, ": {}", message)
+            }
+            Error::DecoderInit { ref message } =>
+                write!(f, "Failed to initialize decoder: {}", message),
+            Error::Search { ref name, ref message } =>
+                write!(f, "Failed to configure search '{}': {}", name, message),
+            Error::Grammar { line: Some(line), ref message } =>
+                write!(f, "Invalid grammar at line {}: {}", line, message),
+            Error::Grammar { line: None, ref message } =>
+                write!(f, "Invalid grammar: {}", message),
+            Error::Utterance { ref message } =>
+                write!(f, "Utterance processing failed: {}", message),
+            Error::Lattice { line: Some(line), ref message } =>
+                write!(f, "Invalid lattice at line {}: {}", line, message),
+            Error::Lattice { line: None, ref message } =>
+                write!(f, "Invalid lattice: {}", message),
+            Error::Audio(ref message) =>
+                write!(f, "Invalid audio: {}", message),
+            Error::Io(ref err) =>
+                write!(f, "I/O error: {}", err),
+            Error::InvalidString(ref message) =>
+                write!(f, "Invalid string: {}", message),
+        }
+    }
+}
+
+impl From<NulError> for Error {
+    fn from(err: NulError) -> Self {
+        Error::InvalidString(err.to_string())
     }
 }
 
+impl From<io::Error> for Error {
+    fn from(err: io::Error) -> Self {
+        Error::Io(err)
+    }
+}
+
+/// Message describing failure of last native call.
+pub(crate) fn native_message() -> String {
+    logging::last_error().unwrap_or_else(|| "see PocketSphinx log for details".to_owned())
+}
+
 pub type Result<T> = std::result::Result<T, Error>;
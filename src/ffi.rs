@@ -0,0 +1,75 @@
+// Declarations missing from `pocketsphinx-sys`.
+
+#![allow(non_camel_case_types)]
+
+use bindings;
+
+use libc::{c_char, c_int, FILE};
+
+pub enum logmath_t {}
+pub enum ps_lattice_t {}
+pub enum ps_latnode_t {}
+pub enum ps_latlink_t {}
+pub enum ps_latnode_iter_t {}
+pub enum ps_latlink_iter_t {}
+
+// Same layout as `bindings::arg_t` whose fields are private.
+#[repr(C)]
+pub struct arg_t {
+    pub name: *const c_char,
+    pub type_: c_int,
+    pub deflt: *const c_char,
+    pub doc: *const c_char,
+}
+
+#[link(name="pocketsphinx")]
+extern "C" {
+    pub fn cmd_ln_parse_file_r(inout_cmdln: *mut bindings::cmd_ln_t,
+                               defn: *const bindings::arg_t,
+                               filename: *const c_char,
+                               strict: c_int)
+                               -> *mut bindings::cmd_ln_t;
+    pub fn cmd_ln_set_str_r(cmdln: *mut bindings::cmd_ln_t, name: *const c_char, str: *const c_char);
+
+    pub fn ps_reinit(ps: *mut bindings::ps_decoder_t, config: *mut bindings::cmd_ln_t) -> c_int;
+
+    pub fn err_set_logfp(stream: *mut FILE) -> *mut FILE;
+
+    pub fn ps_get_logmath(ps: *mut bindings::ps_decoder_t) -> *mut logmath_t;
+    pub fn logmath_get_base(lmath: *mut logmath_t) -> f64;
+    pub fn logmath_get_zero(lmath: *mut logmath_t) -> c_int;
+    pub fn logmath_add(lmath: *mut logmath_t, logb_p: c_int, logb_q: c_int) -> c_int;
+    pub fn logmath_log(lmath: *mut logmath_t, p: f64) -> c_int;
+    pub fn logmath_exp(lmath: *mut logmath_t, logb_p: c_int) -> f64;
+    pub fn logmath_ln_to_log(lmath: *mut logmath_t, log_p: f64) -> c_int;
+    pub fn logmath_log_to_ln(lmath: *mut logmath_t, logb_p: c_int) -> f64;
+    pub fn logmath_log10_to_log(lmath: *mut logmath_t, log_p: f64) -> c_int;
+    pub fn logmath_log_to_log10(lmath: *mut logmath_t, logb_p: c_int) -> f64;
+
+    pub fn ps_get_lattice(ps: *mut bindings::ps_decoder_t) -> *mut ps_lattice_t;
+    pub fn ps_lattice_n_frames(dag: *mut ps_lattice_t) -> c_int;
+    pub fn ps_lattice_bestpath(dag: *mut ps_lattice_t, lmset: *mut bindings::ngram_model_t,
+                               lwf: f32, ascale: f32) -> *mut ps_latlink_t;
+    pub fn ps_lattice_posterior(dag: *mut ps_lattice_t, lmset: *mut bindings::ngram_model_t,
+                                ascale: f32) -> i32;
+    pub fn ps_latnode_iter(dag: *mut ps_lattice_t) -> *mut ps_latnode_iter_t;
+    pub fn ps_latnode_iter_next(itor: *mut ps_latnode_iter_t) -> *mut ps_latnode_iter_t;
+    pub fn ps_latnode_iter_free(itor: *mut ps_latnode_iter_t);
+    pub fn ps_latnode_iter_node(itor: *mut ps_latnode_iter_t) -> *mut ps_latnode_t;
+    pub fn ps_latnode_times(node: *mut ps_latnode_t, out_fef: *mut i16, out_lef: *mut i16) -> c_int;
+    pub fn ps_latnode_word(dag: *mut ps_lattice_t, node: *mut ps_latnode_t) -> *const c_char;
+    pub fn ps_latnode_baseword(dag: *mut ps_lattice_t, node: *mut ps_latnode_t) -> *const c_char;
+    pub fn ps_latnode_exits(node: *mut ps_latnode_t) -> *mut ps_latlink_iter_t;
+    pub fn ps_latnode_entries(node: *mut ps_latnode_t) -> *mut ps_latlink_iter_t;
+    pub fn ps_latnode_prob(dag: *mut ps_lattice_t, node: *mut ps_latnode_t,
+                           out_link: *mut *mut ps_latlink_t) -> i32;
+    pub fn ps_latlink_iter_next(itor: *mut ps_latlink_iter_t) -> *mut ps_latlink_iter_t;
+    pub fn ps_latlink_iter_free(itor: *mut ps_latlink_iter_t);
+    pub fn ps_latlink_iter_link(itor: *mut ps_latlink_iter_t) -> *mut ps_latlink_t;
+    pub fn ps_latlink_times(link: *mut ps_latlink_t, out_sf: *mut i16) -> c_int;
+    pub fn ps_latlink_nodes(link: *mut ps_latlink_t, out_src: *mut *mut ps_latnode_t) -> *mut ps_latnode_t;
+    pub fn ps_latlink_word(dag: *mut ps_lattice_t, link: *mut ps_latlink_t) -> *const c_char;
+    pub fn ps_latlink_baseword(dag: *mut ps_lattice_t, link: *mut ps_latlink_t) -> *const c_char;
+    pub fn ps_latlink_pred(link: *mut ps_latlink_t) -> *mut ps_latlink_t;
+    pub fn ps_latlink_prob(dag: *mut ps_lattice_t, link: *mut ps_latlink_t, out_ascr: *mut i32) -> i32;
+}
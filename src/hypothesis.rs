@@ -0,0 +1,150 @@
+use std::time::Duration;
+
+use super::SegProbs;
+
+/// Recognition result.
+///
+/// Unlike `Seg` and `NBest`, it doesn't borrow decoder.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Hypothesis {
+    pub text: String,
+    pub utt_id: Option<String>,
+    /// Path score.
+    pub score: i32,
+    /// Posterior probability, see `PsDecoder::get_prob`.
+    pub prob: i32,
+    /// Posterior probability in [0, 1]. It is computed only by ngram and fsg
+    /// searches when `-bestpath` is enabled and utterance is finished.
+    pub confidence: Option<f64>,
+    pub n_frames: i32,
+    pub duration: Duration,
+    pub words: Vec<WordSegment>,
+}
+
+/// Word of recognition result, owned counterpart of `Seg`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct WordSegment {
+    pub word: String,
+    pub start_frame: i32,
+    pub end_frame: i32,
+    pub start_time: Duration,
+    pub end_time: Duration,
+    pub probs: SegProbs,
+    /// See `Seg::confidence`.
+    pub confidence: Option<f64>,
+}
+
+/// N-best hypothesis which doesn't borrow decoder, see `PsDecoder::nbest_list`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct NBestHypothesis {
+    pub text: String,
+    pub score: i32,
+    /// Probability of hypothesis among returned ones, they sum up to 1.
+    pub posterior: f64,
+    pub words: Vec<WordSegment>,
+}
+
+/// Change of hypothesis text during utterance.
+///
+/// `stable` is leading words which are the same as in previous update,
+/// `volatile` is the rest which is likely to change with more audio.
+/// Final update has all words stable.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct HypothesisUpdate {
+    pub text: String,
+    pub stable: String,
+    pub volatile: String,
+    pub is_final: bool,
+}
+
+impl HypothesisUpdate {
+    pub(crate) fn interim(previous: &str, text: String) -> Self {
+        let words: Vec<&str> = text.split_whitespace().collect();
+        let common = words.iter()
+            .zip(previous.split_whitespace())
+            .take_while(|&(word, previous)| *word == previous)
+            .count();
+        let stable = words[..common].join(" ");
+        let volatile = words[common..].join(" ");
+        HypothesisUpdate { text, stable, volatile, is_final: false }
+    }
+
+    pub(crate) fn last(text: String) -> Self {
+        HypothesisUpdate { stable: text.clone(), volatile: String::new(), text, is_final: true }
+    }
+}
+
+// Sphinx default of `-frate`.
+pub(crate) const DEFAULT_FRAME_RATE: i32 = 100;
+
+// Invalid frame rate falls back to default one instead of dividing by zero.
+pub(crate) fn frames_to_duration(frames: i32, frate: i32) -> Duration {
+    if frames <= 0 {
+        return Duration::from_secs(0);
+    }
+    let frate = if frate > 0 { frate } else { DEFAULT_FRAME_RATE };
+    let (frames, frate) = (frames as u64, frate as u64);
+    Duration::new(frames / frate, ((frames % frate) * 1_000_000_000 / frate) as u32)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parts(stable: &str, volatile: &str) -> (String, String) {
+        (stable.to_owned(), volatile.to_owned())
+    }
+
+    fn split(previous: &str, text: &str) -> (String, String) {
+        let update = HypothesisUpdate::interim(previous, text.to_owned());
+        assert_eq!(update.text, text);
+        assert!(!update.is_final);
+        (update.stable, update.volatile)
+    }
+
+    #[test]
+    fn first_update_is_volatile() {
+        assert_eq!(split("", "hello world"), parts("", "hello world"));
+    }
+
+    #[test]
+    fn changed_suffix_is_volatile() {
+        assert_eq!(split("go to the", "go to the store"), parts("go to the", "store"));
+        assert_eq!(split("go to the store", "go to the stove"), parts("go to the", "stove"));
+        assert_eq!(split("go two", "go to the"), parts("go", "to the"));
+    }
+
+    #[test]
+    fn shrinking_hypothesis() {
+        assert_eq!(split("go to the store", "go to"), parts("go to", ""));
+        assert_eq!(split("go to the store", "no"), parts("", "no"));
+    }
+
+    #[test]
+    fn empty_text() {
+        assert_eq!(split("", ""), parts("", ""));
+        assert_eq!(split("hello", ""), parts("", ""));
+    }
+
+    #[test]
+    fn whitespace_is_normalized() {
+        assert_eq!(split("hello  world", " hello world again"), parts("hello world", "again"));
+    }
+
+    #[test]
+    fn last_update_is_stable() {
+        let update = HypothesisUpdate::last("hello world".to_owned());
+        assert_eq!(update.stable, "hello world");
+        assert_eq!(update.volatile, "");
+        assert!(update.is_final);
+    }
+
+    #[test]
+    fn frames_to_duration_uses_frame_rate() {
+        assert_eq!(frames_to_duration(150, 100), Duration::from_millis(1500));
+        assert_eq!(frames_to_duration(1, 3), Duration::new(0, 333_333_333));
+        assert_eq!(frames_to_duration(-1, 100), Duration::from_secs(0));
+        assert_eq!(frames_to_duration(100, 0), Duration::from_secs(1));
+        assert_eq!(frames_to_duration(100, -5), Duration::from_secs(1));
+    }
+}
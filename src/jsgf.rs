@@ -1,17 +1,19 @@
 use bindings;
 
 use std;
+use std::borrow::Cow;
 use std::ptr;
 use std::ffi::{CStr, CString, OsStr};
 
 use std::os::unix::ffi::OsStrExt;
 
-use super::{Error, Result};
+use super::{Error, Result, native_message};
 
 pub mod internal {
     use std;
     use bindings;
     use libc::c_char;
+    use std::borrow::Cow;
     use std::ffi::CStr;
 
     #[derive(Clone)]
@@ -27,7 +29,7 @@ pub mod internal {
     }
 
     impl<'a> Iterator for Tags<'a> {
-        type Item = &'a str;
+        type Item = Cow<'a, str>;
 
         fn next(&mut self) -> Option<Self::Item> {
             if self.node.is_null() {
@@ -36,7 +38,7 @@ pub mod internal {
 
             let tag = unsafe { CStr::from_ptr((*self.node).data.as_ptr() as *const c_char) };
             self.node = unsafe { (*self.node).next };
-            Some(tag.to_str().unwrap())
+            Some(tag.to_string_lossy())
         }
     }
 
@@ -51,8 +53,8 @@ pub mod internal {
             Atom { raw: raw, _marker: std::marker::PhantomData }
         }
 
-        pub fn name(&self) -> &'a str {
-            unsafe { CStr::from_ptr((*self.raw).name).to_str().unwrap() }
+        pub fn name(&self) -> Cow<'a, str> {
+            unsafe { CStr::from_ptr((*self.raw).name).to_string_lossy() }
         }
 
         pub fn tags(&self) -> Tags<'a>  {
@@ -142,9 +144,9 @@ impl<'a> Rule<'a> {
         Rule { raw: raw, _marker: std::marker::PhantomData }
     }
 
-    pub fn name(&self) -> &'a str {
+    pub fn name(&self) -> Cow<'a, str> {
         let name_c = unsafe { bindings::jsgf_rule_name(self.raw) };
-        unsafe { CStr::from_ptr(name_c) }.to_str().unwrap()
+        unsafe { CStr::from_ptr(name_c) }.to_string_lossy()
     }
 
     pub fn is_public(&self) -> bool {
@@ -190,35 +192,45 @@ impl<'a> Iterator for Rules<'a> {
     }
 }
 
+// Parser reports errors like "syntax error at line 3 current token ...".
+fn grammar_error() -> Error {
+    let message = native_message();
+    let line = message.find("at line ").and_then(|pos| {
+        let digits: String = message[pos + 8..].chars().take_while(|c| c.is_ascii_digit()).collect();
+        digits.parse().ok()
+    });
+    Error::Grammar { line, message }
+}
+
 pub struct Jsgf {
     raw: *mut bindings::jsgf_t,
 }
 
 impl Jsgf {
     pub fn parse_file(filename: &OsStr) -> Result<Self> {
-        let filename_c = CString::new(filename.as_bytes()).unwrap();
+        let filename_c = CString::new(filename.as_bytes())?;
         let raw = unsafe { bindings::jsgf_parse_file(filename_c.as_ptr(), ptr::null()) };
         if raw.is_null() {
-            Err(Error)
+            Err(grammar_error())
         } else {
             Ok(Jsgf { raw: raw })
         }
     }
 
     pub fn parse_string(s: &str) -> Result<Self> {
-        let s_c = CString::new(s.as_bytes()).unwrap();
+        let s_c = CString::new(s.as_bytes())?;
         let raw = unsafe { bindings::jsgf_parse_string(s_c.as_ptr(), ptr::null()) };
         if raw.is_null() {
-            Err(Error)
+            Err(grammar_error())
         } else {
             Ok(Jsgf { raw: raw })
         }
     }
 
-    pub fn name(&self) -> &str {
+    pub fn name(&self) -> Cow<'_, str> {
         let name_c = unsafe { bindings::jsgf_grammar_name(self.raw) };
         assert!(!name_c.is_null());
-        unsafe { CStr::from_ptr(name_c) }.to_str().unwrap()
+        unsafe { CStr::from_ptr(name_c) }.to_string_lossy()
     }
 
     pub fn rules(&self) -> Rules {
@@ -231,13 +243,13 @@ impl Jsgf {
         Rule::new(unsafe { bindings::jsgf_get_public_rule(self.raw) })
     }
 
-    pub fn rule<'a>(&'a self, name: &str) -> Option<Rule<'a>> {
-        let name_c = CString::new(name).unwrap();
+    pub fn rule<'a>(&'a self, name: &str) -> Result<Option<Rule<'a>>> {
+        let name_c = CString::new(name)?;
         let raw_rule = unsafe { bindings::jsgf_get_rule(self.raw, name_c.as_ptr()) };
         if raw_rule.is_null() {
-            None
+            Ok(None)
         } else {
-            Some(Rule::new(raw_rule))
+            Ok(Some(Rule::new(raw_rule)))
         }
     }
 }
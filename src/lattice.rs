@@ -0,0 +1,301 @@
+use bindings;
+
+use std;
+use std::borrow::Cow;
+use std::ffi::CStr;
+use std::ptr;
+use std::time::Duration;
+
+use libc::c_char;
+
+use ffi;
+use super::{PsDecoder, frames_to_duration};
+
+/// Word graph of last utterance.
+///
+/// Node is word hypothesis with start frame and range of possible end
+/// frames, link connects word with the next one. Scores and probabilities
+/// are logarithms, see `LogMath`.
+pub struct Lattice<'a> {
+    raw: *mut ffi::ps_lattice_t,
+    pub(crate) decoder: &'a PsDecoder,
+}
+
+impl PsDecoder {
+    /// Returns lattice of last utterance, it isn't available for all searches.
+    ///
+    /// Decoder is borrowed mutably, so it can't be used while lattice is alive.
+    pub fn lattice(&mut self) -> Option<Lattice<'_>> {
+        // SAFETY: native lattice is owned by search and is freed or rebuilt
+        // by the next decoding call (even `ps_get_prob` may rebuild it), so
+        // lattice, its nodes and links must not outlive exclusive borrow of
+        // decoder. Mutable borrow also prevents aliasing lattices, whose
+        // `posterior` and `best_path` modify native lattice.
+        let raw = unsafe { ffi::ps_get_lattice(self.raw) };
+        if raw.is_null() {
+            None
+        } else {
+            Some(Lattice { raw, decoder: self })
+        }
+    }
+}
+
+unsafe fn lossy_str<'a>(ptr: *const c_char) -> Cow<'a, str> {
+    if ptr.is_null() {
+        Cow::Borrowed("")
+    } else {
+        CStr::from_ptr(ptr).to_string_lossy()
+    }
+}
+
+impl<'a> Lattice<'a> {
+    pub fn n_frames(&self) -> i32 {
+        unsafe { ffi::ps_lattice_n_frames(self.raw) }
+    }
+
+    pub fn nodes(&self) -> LatNodeIter<'_> {
+        LatNodeIter {
+            raw: unsafe { ffi::ps_latnode_iter(self.raw) },
+            lattice: self.raw,
+            frate: self.decoder.frame_rate(),
+            _marker: std::marker::PhantomData,
+        }
+    }
+
+    // Language model of current search, null for searches without it.
+    fn lm(&self) -> *mut bindings::ngram_model_t {
+        unsafe {
+            let search = bindings::ps_get_search(self.decoder.raw);
+            if search.is_null() {
+                ptr::null_mut()
+            } else {
+                bindings::ps_get_lm(self.decoder.raw, search)
+            }
+        }
+    }
+
+    fn config_float(&self, name: &str, default: f64) -> f32 {
+        match self.decoder.config.try_get_float(name) {
+            Ok(Some(value)) => value as f32,
+            _ => default as f32,
+        }
+    }
+
+    /// Computes posterior probabilities of links and nodes with decoder's
+    /// `-ascale`, returns log probability of utterance.
+    pub fn posterior(&mut self) -> i32 {
+        let ascale = self.config_float("-ascale", 20.0);
+        self.posterior_with(ascale)
+    }
+
+    pub fn posterior_with(&mut self, ascale: f32) -> i32 {
+        unsafe { ffi::ps_lattice_posterior(self.raw, self.lm(), ascale) }
+    }
+
+    /// Finds best path with language weight `-bestpathlw` relative to `-lw`
+    /// and `-ascale`, like decoder does for `-bestpath`.
+    pub fn best_path(&mut self) -> Vec<LatLink<'_>> {
+        let lw_ratio = self.config_float("-bestpathlw", 9.5) / self.config_float("-lw", 6.5);
+        let ascale = self.config_float("-ascale", 20.0);
+        self.best_path_with(lw_ratio, ascale)
+    }
+
+    /// Finds best path, returns its links from the first one. Each link
+    /// carries word of node it starts from.
+    pub fn best_path_with(&mut self, lw_ratio: f32, ascale: f32) -> Vec<LatLink<'_>> {
+        let mut raw = unsafe { ffi::ps_lattice_bestpath(self.raw, self.lm(), lw_ratio, ascale) };
+        let frate = self.decoder.frame_rate();
+        let mut path = Vec::new();
+        while !raw.is_null() {
+            path.push(LatLink::new(raw, self.raw, frate));
+            raw = unsafe { ffi::ps_latlink_pred(raw) };
+        }
+        path.reverse();
+        path
+    }
+}
+
+#[derive(Clone, Copy)]
+pub struct LatNode<'a> {
+    pub(crate) raw: *mut ffi::ps_latnode_t,
+    lattice: *mut ffi::ps_lattice_t,
+    frate: i32,
+    _marker: std::marker::PhantomData<&'a Lattice<'a>>,
+}
+
+impl<'a> LatNode<'a> {
+    fn new(raw: *mut ffi::ps_latnode_t, lattice: *mut ffi::ps_lattice_t, frate: i32) -> Self {
+        LatNode { raw, lattice, frate, _marker: std::marker::PhantomData }
+    }
+
+    pub fn word(&self) -> Cow<'a, str> {
+        unsafe { lossy_str(ffi::ps_latnode_word(self.lattice, self.raw)) }
+    }
+
+    /// Word without alternative pronunciation suffix.
+    pub fn baseword(&self) -> Cow<'a, str> {
+        unsafe { lossy_str(ffi::ps_latnode_baseword(self.lattice, self.raw)) }
+    }
+
+    /// Returns start frame along with first and last possible end frames.
+    pub fn frames(&self) -> (i32, i32, i32) {
+        let mut first_end: i16 = 0;
+        let mut last_end: i16 = 0;
+        let start = unsafe { ffi::ps_latnode_times(self.raw, &mut first_end, &mut last_end) };
+        (start, first_end as i32, last_end as i32)
+    }
+
+    pub fn start_time(&self) -> Duration {
+        frames_to_duration(self.frames().0, self.frate)
+    }
+
+    /// Posterior probability of the best link leaving node, valid after
+    /// `Lattice::posterior`.
+    pub fn prob(&self) -> i32 {
+        unsafe { ffi::ps_latnode_prob(self.lattice, self.raw, ptr::null_mut()) }
+    }
+
+    pub fn exits(&self) -> LatLinkIter<'a> {
+        LatLinkIter::new(unsafe { ffi::ps_latnode_exits(self.raw) }, self.lattice, self.frate)
+    }
+
+    pub fn entries(&self) -> LatLinkIter<'a> {
+        LatLinkIter::new(unsafe { ffi::ps_latnode_entries(self.raw) }, self.lattice, self.frate)
+    }
+}
+
+#[derive(Clone, Copy)]
+pub struct LatLink<'a> {
+    raw: *mut ffi::ps_latlink_t,
+    lattice: *mut ffi::ps_lattice_t,
+    frate: i32,
+    _marker: std::marker::PhantomData<&'a Lattice<'a>>,
+}
+
+impl<'a> LatLink<'a> {
+    fn new(raw: *mut ffi::ps_latlink_t, lattice: *mut ffi::ps_lattice_t, frate: i32) -> Self {
+        LatLink { raw, lattice, frate, _marker: std::marker::PhantomData }
+    }
+
+    /// Word of source node.
+    pub fn word(&self) -> Cow<'a, str> {
+        unsafe { lossy_str(ffi::ps_latlink_word(self.lattice, self.raw)) }
+    }
+
+    pub fn baseword(&self) -> Cow<'a, str> {
+        unsafe { lossy_str(ffi::ps_latlink_baseword(self.lattice, self.raw)) }
+    }
+
+    /// Start and end frames of source node word.
+    pub fn frames(&self) -> (i32, i32) {
+        let mut start: i16 = 0;
+        let end = unsafe { ffi::ps_latlink_times(self.raw, &mut start) };
+        (start as i32, end)
+    }
+
+    pub fn start_time(&self) -> Duration {
+        frames_to_duration(self.frames().0, self.frate)
+    }
+
+    /// End frame is included into link, so it is time when it ends.
+    pub fn end_time(&self) -> Duration {
+        frames_to_duration(self.frames().1 + 1, self.frate)
+    }
+
+    /// Returns source node, which is absent for initial link, and destination node.
+    pub fn nodes(&self) -> (Option<LatNode<'a>>, LatNode<'a>) {
+        let mut source = ptr::null_mut();
+        let destination = unsafe { ffi::ps_latlink_nodes(self.raw, &mut source) };
+        let source = if source.is_null() {
+            None
+        } else {
+            Some(LatNode::new(source, self.lattice, self.frate))
+        };
+        (source, LatNode::new(destination, self.lattice, self.frate))
+    }
+
+    pub fn acoustic_score(&self) -> i32 {
+        let mut ascr = 0;
+        unsafe { ffi::ps_latlink_prob(self.lattice, self.raw, &mut ascr) };
+        ascr
+    }
+
+    /// Posterior probability, valid after `Lattice::posterior`.
+    pub fn posterior(&self) -> i32 {
+        unsafe { ffi::ps_latlink_prob(self.lattice, self.raw, ptr::null_mut()) }
+    }
+
+    /// Previous link on best path, valid after `Lattice::best_path`.
+    pub fn pred(&self) -> Option<LatLink<'a>> {
+        let raw = unsafe { ffi::ps_latlink_pred(self.raw) };
+        if raw.is_null() {
+            None
+        } else {
+            Some(LatLink::new(raw, self.lattice, self.frate))
+        }
+    }
+}
+
+pub struct LatNodeIter<'a> {
+    raw: *mut ffi::ps_latnode_iter_t,
+    lattice: *mut ffi::ps_lattice_t,
+    frate: i32,
+    _marker: std::marker::PhantomData<&'a Lattice<'a>>,
+}
+
+impl<'a> Drop for LatNodeIter<'a> {
+    fn drop(&mut self) {
+        if !self.raw.is_null() {
+            unsafe { ffi::ps_latnode_iter_free(self.raw) };
+        }
+    }
+}
+
+impl<'a> Iterator for LatNodeIter<'a> {
+    type Item = LatNode<'a>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.raw.is_null() {
+            None
+        } else {
+            let node = unsafe { ffi::ps_latnode_iter_node(self.raw) };
+            self.raw = unsafe { ffi::ps_latnode_iter_next(self.raw) };
+            Some(LatNode::new(node, self.lattice, self.frate))
+        }
+    }
+}
+
+pub struct LatLinkIter<'a> {
+    raw: *mut ffi::ps_latlink_iter_t,
+    lattice: *mut ffi::ps_lattice_t,
+    frate: i32,
+    _marker: std::marker::PhantomData<&'a Lattice<'a>>,
+}
+
+impl<'a> LatLinkIter<'a> {
+    fn new(raw: *mut ffi::ps_latlink_iter_t, lattice: *mut ffi::ps_lattice_t, frate: i32) -> Self {
+        LatLinkIter { raw, lattice, frate, _marker: std::marker::PhantomData }
+    }
+}
+
+impl<'a> Drop for LatLinkIter<'a> {
+    fn drop(&mut self) {
+        if !self.raw.is_null() {
+            unsafe { ffi::ps_latlink_iter_free(self.raw) };
+        }
+    }
+}
+
+impl<'a> Iterator for LatLinkIter<'a> {
+    type Item = LatLink<'a>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.raw.is_null() {
+            None
+        } else {
+            let link = unsafe { ffi::ps_latlink_iter_link(self.raw) };
+            self.raw = unsafe { ffi::ps_latlink_iter_next(self.raw) };
+            Some(LatLink::new(link, self.lattice, self.frate))
+        }
+    }
+}
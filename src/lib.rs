@@ -1,19 +1,64 @@
 extern crate libc;
 extern crate pocketsphinx_sys as bindings;
+#[cfg(feature = "serde")]
+extern crate serde;
+#[cfg(feature = "log")]
+extern crate log;
+#[cfg(feature = "async")]
+extern crate futures;
 
+use std::borrow::Cow;
+use std::cell::Cell;
+use std::fs;
 use std::ptr;
-use std::ffi::{CStr, CString};
-use libc::c_char;
+use std::ffi::{CStr, CString, OsStr};
+use std::path::Path;
+use libc::{c_char, c_long};
+
+use std::os::unix::ffi::OsStrExt;
 
 pub use search::*;
 pub use nbest::*;
 pub use jsgf::*;
 pub use error::*;
+pub use config::*;
+pub use args::*;
+pub use logging::*;
+pub use pool::*;
+pub use hypothesis::*;
+pub use utterance::*;
+pub use logmath::*;
+pub use audio::*;
+pub use resample::*;
+pub use decode::*;
+pub use continuous::*;
+pub use lattice::*;
+pub use slf::*;
+#[cfg(feature = "async")]
+pub use stream::*;
 
 mod search;
 mod nbest;
 mod jsgf;
 mod error;
+mod config;
+mod args;
+mod ffi;
+mod logging;
+mod pool;
+mod hypothesis;
+mod utterance;
+mod logmath;
+mod audio;
+mod resample;
+mod decode;
+mod continuous;
+mod lattice;
+mod slf;
+#[cfg(feature = "serde")]
+mod serde_impls;
+#[cfg(feature = "async")]
+mod stream;
 
 pub struct CmdLn {
     raw: *mut bindings::cmd_ln_t,
@@ -23,7 +68,62 @@ impl CmdLn {
     pub fn init(strict: bool, args: &[&str]) -> Result<Self> {
         // Sphinx assumes that `args` are valid as long as returned
         // `cmd_ln_t` is alive, so copy them.
-        let c_args: Vec<_> = args.iter().map(|s| CString::new(*s).unwrap()).collect();
+        let c_args = args.iter().map(|s| CString::new(*s)).collect::<std::result::Result<Vec<_>, _>>()?;
+        CmdLn::parse_c_args(strict, &c_args)
+    }
+
+    /// Loads arguments from Sphinx argument file like `feat.params`.
+    pub fn from_file<P: AsRef<Path>>(path: P, strict: bool) -> Result<Self> {
+        let path = path.as_ref();
+        let contents = fs::read(path).map_err(|err| Error::ConfigParse {
+            line: None,
+            flag: None,
+            message: format!("can't read {}: {}", path.display(), err),
+        })?;
+        args::check_args_file(&String::from_utf8_lossy(&contents), strict)?;
+
+        let path_c = CString::new(path.as_os_str().as_bytes())?;
+        let raw = unsafe {
+            ffi::cmd_ln_parse_file_r(ptr::null_mut(), bindings::ps_args(),
+                                     path_c.as_ptr(), strict as i32)
+        };
+        if raw.is_null() {
+            return Err(Error::ConfigParse { line: None, flag: None, message: native_message() });
+        }
+        Ok(CmdLn { raw })
+    }
+
+    /// Overrides arguments with ones set in `config`.
+    pub fn merge(&mut self, config: &Config) -> Result<()> {
+        for (name, value) in config.args() {
+            let error = |message: &str| Error::ConfigParse {
+                line: None,
+                flag: Some(name.to_owned()),
+                message: message.to_owned(),
+            };
+            let value = value.to_str().ok_or_else(|| error("value is not valid UTF-8"))?;
+            match arg_type(name) {
+                Some(ArgType::String) => self.set_str(name, Some(value))?,
+                Some(ArgType::Integer) => {
+                    let value = args::parse_int(value).ok_or_else(|| error("invalid integer"))?;
+                    self.set_int(name, value)?
+                }
+                Some(ArgType::Floating) => {
+                    let value = args::parse_float(value).ok_or_else(|| error("invalid number"))?;
+                    self.set_float(name, value)?
+                }
+                Some(ArgType::Boolean) => {
+                    let value = args::parse_boolean(value).ok_or_else(|| error("invalid boolean"))?;
+                    self.set_boolean(name, value)?
+                }
+                Some(ArgType::StringList) => return Err(error("string lists can't be overridden")),
+                None => return Err(error("unknown argument")),
+            }
+        }
+        Ok(())
+    }
+
+    fn parse_c_args(strict: bool, c_args: &[CString]) -> Result<Self> {
         let args_ptrs: Vec<_> = c_args.iter().map(|s| s.as_ptr()).collect();
         let raw = unsafe {
             bindings::cmd_ln_parse_r(ptr::null_mut(),
@@ -33,46 +133,213 @@ impl CmdLn {
                                      strict as i32)
         };
         if raw.is_null() {
-            return Err(Error);
+            return Err(Error::ConfigParse { line: None, flag: None, message: native_message() });
         }
         Ok(CmdLn{raw: raw})
     }
 
+    #[deprecated(note = "panics on invalid names and values, use `try_get_str`")]
     pub unsafe fn get_str(&self, name: &str) -> &str {
         let c_str = bindings::cmd_ln_str_r(self.raw, CString::new(name).unwrap().as_ptr());
         CStr::from_ptr(c_str).to_str().unwrap()
     }
 
+    #[deprecated(note = "panics on invalid names and values, use `try_get_int`")]
     pub unsafe fn get_int(&self, name: &str) -> i64 {
         bindings::cmd_ln_int_r(self.raw, CString::new(name).unwrap().as_ptr())
     }
 
+    #[deprecated(note = "panics on invalid names and values, use `try_get_float`")]
     pub unsafe fn get_float(&self, name: &str) -> f64 {
         bindings::cmd_ln_float_r(self.raw, CString::new(name).unwrap().as_ptr())
     }
 
     pub fn exists(&self, name: &str) -> bool {
-        let res = unsafe { bindings::cmd_ln_exists_r(self.raw, CString::new(name).unwrap().as_ptr()) };
+        // Name with NUL byte can't be defined.
+        let name_c = match CString::new(name) {
+            Ok(name_c) => name_c,
+            Err(_) => return false,
+        };
+        let res = unsafe { bindings::cmd_ln_exists_r(self.raw, name_c.as_ptr()) };
         res != 0
     }
 
+    #[deprecated(note = "panics on invalid names and values, use `try_get_boolean`")]
     pub unsafe fn get_boolean(&self, name: &str) -> bool {
-        self.get_int(name) != 0
+        bindings::cmd_ln_boolean_r(self.raw, CString::new(name).unwrap().as_ptr())
     }
 
+    #[deprecated(note = "panics on invalid names and values, use `try_get_int`")]
     pub unsafe fn get_int32(&self, name: &str) -> i32 {
-        self.get_int(name) as i32
+        bindings::cmd_ln_int32_r(self.raw, CString::new(name).unwrap().as_ptr())
     }
 
+    #[deprecated(note = "panics on invalid names and values, use `try_get_float`")]
     pub unsafe fn get_float32(&self, name: &str) -> f32 {
-        self.get_float(name) as f32
+        bindings::cmd_ln_float32_r(self.raw, CString::new(name).unwrap().as_ptr())
     }
 
+    #[deprecated(note = "panics on invalid names and values, use `try_get_float`")]
     pub unsafe fn get_float64(&self, name: &str) -> f64 {
-        self.get_float(name) as f64
+        bindings::cmd_ln_float64_r(self.raw, CString::new(name).unwrap().as_ptr())
+    }
+
+    pub fn try_get_str(&self, name: &str) -> Result<Option<Cow<'_, str>>> {
+        let name_c = match self.checked_name(name, ArgType::String)? {
+            Some(name_c) => name_c,
+            None => return Ok(None),
+        };
+        let value_c = unsafe { bindings::cmd_ln_str_r(self.raw, name_c.as_ptr()) };
+        if value_c.is_null() {
+            return Ok(None);
+        }
+        Ok(Some(unsafe { CStr::from_ptr(value_c) }.to_string_lossy()))
+    }
+
+    pub fn try_get_int(&self, name: &str) -> Result<Option<i64>> {
+        Ok(self.checked_name(name, ArgType::Integer)?.map(|name_c| {
+            unsafe { bindings::cmd_ln_int_r(self.raw, name_c.as_ptr()) as i64 }
+        }))
+    }
+
+    pub fn try_get_float(&self, name: &str) -> Result<Option<f64>> {
+        Ok(self.checked_name(name, ArgType::Floating)?.map(|name_c| {
+            unsafe { bindings::cmd_ln_float_r(self.raw, name_c.as_ptr()) }
+        }))
+    }
+
+    pub fn try_get_boolean(&self, name: &str) -> Result<Option<bool>> {
+        Ok(self.checked_name(name, ArgType::Boolean)?.map(|name_c| {
+            unsafe { bindings::cmd_ln_boolean_r(self.raw, name_c.as_ptr()) }
+        }))
+    }
+
+    pub fn value(&self, name: &str) -> Result<Option<ArgValue>> {
+        let arg_type = match arg_type(name) {
+            Some(arg_type) => arg_type,
+            None => return Ok(None),
+        };
+        let name_c = match self.checked_name(name, arg_type)? {
+            Some(name_c) => name_c,
+            None => return Ok(None),
+        };
+        let value = unsafe {
+            match arg_type {
+                ArgType::Integer => ArgValue::Integer(bindings::cmd_ln_int_r(self.raw, name_c.as_ptr()) as i64),
+                ArgType::Floating => ArgValue::Floating(bindings::cmd_ln_float_r(self.raw, name_c.as_ptr())),
+                ArgType::Boolean => ArgValue::Boolean(bindings::cmd_ln_boolean_r(self.raw, name_c.as_ptr())),
+                ArgType::String => {
+                    let value_c = bindings::cmd_ln_str_r(self.raw, name_c.as_ptr());
+                    ArgValue::String(lossy_string(value_c))
+                }
+                ArgType::StringList => {
+                    // List is NULL-terminated array of strings, which is NULL itself if not set.
+                    let access = bindings::cmd_ln_access_r(self.raw, name_c.as_ptr());
+                    let mut item = (*access).as_ptr() as *const *const c_char;
+                    let mut list = Vec::new();
+                    while !item.is_null() && !(*item).is_null() {
+                        list.extend(lossy_string(*item));
+                        item = item.offset(1);
+                    }
+                    ArgValue::StringList(list)
+                }
+            }
+        };
+        Ok(Some(value))
+    }
+
+    /// Returns current values of all arguments.
+    pub fn dump(&self) -> Result<Vec<(&'static str, ArgValue)>> {
+        let mut values = Vec::new();
+        for def in arg_defs() {
+            if let Some(value) = self.value(def.name)? {
+                values.push((def.name, value));
+            }
+        }
+        Ok(values)
+    }
+
+    pub fn set_str(&mut self, name: &str, value: Option<&str>) -> Result<()> {
+        let name_c = self.existing_name(name, ArgType::String)?;
+        // Value is copied by Sphinx, so it is enough for it to live during call.
+        let value_c = match value {
+            Some(value) => Some(CString::new(value)?),
+            None => None,
+        };
+        unsafe {
+            ffi::cmd_ln_set_str_r(self.raw, name_c.as_ptr(),
+                                  value_c.as_ref().map_or(ptr::null(), |c| c.as_ptr()))
+        };
+        Ok(())
+    }
+
+    pub fn set_int(&mut self, name: &str, value: i64) -> Result<()> {
+        let name_c = self.existing_name(name, ArgType::Integer)?;
+        unsafe { *(self.access_mut(&name_c) as *mut c_long) = value as c_long };
+        Ok(())
+    }
+
+    pub fn set_float(&mut self, name: &str, value: f64) -> Result<()> {
+        let name_c = self.existing_name(name, ArgType::Floating)?;
+        unsafe { *(self.access_mut(&name_c) as *mut f64) = value };
+        Ok(())
+    }
+
+    pub fn set_boolean(&mut self, name: &str, value: bool) -> Result<()> {
+        let name_c = self.existing_name(name, ArgType::Boolean)?;
+        unsafe { *(self.access_mut(&name_c) as *mut c_long) = value as c_long };
+        Ok(())
+    }
+
+    // Counterpart of `cmd_ln_set_int_r` and `cmd_ln_set_float_r` macros,
+    // name must be checked to exist.
+    unsafe fn access_mut(&mut self, name_c: &CStr) -> *mut bindings::anytype_t {
+        bindings::cmd_ln_access_r(self.raw, name_c.as_ptr()) as *mut bindings::anytype_t
+    }
+
+    // Path may be non-UTF-8, so it is not converted into string.
+    fn path_value(&self, name: &str) -> Option<&Path> {
+        let name_c = self.checked_name(name, ArgType::String).ok()??;
+        let value_c = unsafe { bindings::cmd_ln_str_r(self.raw, name_c.as_ptr()) };
+        if value_c.is_null() {
+            None
+        } else {
+            Some(Path::new(OsStr::from_bytes(unsafe { CStr::from_ptr(value_c) }.to_bytes())))
+        }
+    }
+
+    // Returns `None` if argument is not set, fails if it is declared with another type.
+    fn checked_name(&self, name: &str, expected: ArgType) -> Result<Option<CString>> {
+        let name_c = CString::new(name)?;
+        if unsafe { bindings::cmd_ln_exists_r(self.raw, name_c.as_ptr()) } == 0 {
+            return Ok(None);
+        }
+        match arg_type(name) {
+            Some(arg_type) if arg_type == expected => Ok(Some(name_c)),
+            arg_type => Err(Error::ConfigParse {
+                line: None,
+                flag: Some(name.to_owned()),
+                message: match arg_type {
+                    Some(arg_type) => format!("argument is of {:?} type", arg_type),
+                    None => "unknown argument".to_owned(),
+                },
+            }),
+        }
+    }
+
+    // Same as `checked_name`, but fails if argument is not set.
+    fn existing_name(&self, name: &str, expected: ArgType) -> Result<CString> {
+        self.checked_name(name, expected)?.ok_or_else(|| Error::ConfigParse {
+            line: None,
+            flag: Some(name.to_owned()),
+            message: "unknown argument".to_owned(),
+        })
     }
 }
 
+// Configuration is only shared with decoder owning it.
+unsafe impl Send for CmdLn {}
+
 impl Drop for CmdLn {
     fn drop(&mut self) {
         if !self.raw.is_null() {
@@ -82,28 +349,70 @@ impl Drop for CmdLn {
 }
 
 
+unsafe fn lossy_string(c_str: *const c_char) -> Option<String> {
+    if c_str.is_null() {
+        None
+    } else {
+        Some(CStr::from_ptr(c_str).to_string_lossy().into_owned())
+    }
+}
+
 pub struct PsDecoder {
     raw: *mut bindings::ps_decoder_t,
+    // Decoder retains configuration, keep it to allow changing it later.
+    config: CmdLn,
+    // Segment posteriors are only computed when utterance is over.
+    in_utt: Cell<bool>,
 }
 
 impl PsDecoder {
-    pub fn init(config: CmdLn) -> Self {
+    pub fn new(config: CmdLn) -> Result<Self> {
+        check_model_paths(&config)?;
+        release_redirected_log(&config);
         let raw = unsafe { bindings::ps_init(config.raw) };
-        assert!(!raw.is_null());
-        PsDecoder{raw: raw}
+        if raw.is_null() {
+            return Err(Error::DecoderInit { message: native_message() });
+        }
+        Ok(PsDecoder { raw, config, in_utt: Cell::new(false) })
+    }
+
+    #[deprecated(note = "panics on failure, use `new`")]
+    pub fn init(config: CmdLn) -> Self {
+        PsDecoder::new(config).expect("failed to initialize decoder")
+    }
+
+    pub fn config(&self) -> &CmdLn {
+        &self.config
+    }
+
+    /// Changes made to configuration take effect after `reinit`.
+    pub fn config_mut(&mut self) -> &mut CmdLn {
+        &mut self.config
+    }
+
+    pub fn reinit(&mut self) -> Result<()> {
+        release_redirected_log(&self.config);
+        let code = unsafe { ffi::ps_reinit(self.raw, ptr::null_mut()) };
+        if code < 0 { return Err(Error::DecoderInit { message: native_message() }); }
+        Ok(())
+    }
+
+    pub fn reinit_with(&mut self, config: CmdLn) -> Result<()> {
+        release_redirected_log(&config);
+        let code = unsafe { ffi::ps_reinit(self.raw, config.raw) };
+        // Decoder retains new configuration even if it fails to apply it.
+        self.config = config;
+        if code < 0 { return Err(Error::DecoderInit { message: native_message() }); }
+        Ok(())
     }
 
     pub fn start_utt(&self, utt_id: Option<&str>) -> Result<()>  {
-        let (_id_cstr, id_ptr) = utt_id.map_or_else(
-            ||  { (CString::new("").unwrap(), ptr::null()) },
-            |s| {
-                let cstr = CString::new(s).unwrap();
-                let cptr = cstr.as_ptr();
-                (cstr, cptr)
-            }
-        );
+        let id_c = utt_id.map(CString::new).transpose()?;
+        let id_ptr = id_c.as_ref().map_or(ptr::null(), |c| c.as_ptr());
         let code = unsafe { bindings::ps_start_utt(self.raw, id_ptr) };
-        if code == 0 { Ok(()) } else { Err(Error) }
+        if code != 0 { return Err(Error::Utterance { message: native_message() }); }
+        self.in_utt.set(true);
+        Ok(())
     }
 
     pub fn process_raw(&self,
@@ -114,29 +423,46 @@ impl PsDecoder {
             bindings::ps_process_raw(self.raw, data.as_ptr(), data.len(),
                                      no_search as i32, full_utt as i32)
         };
-        if frames < 0 { return Err(Error); }
+        if frames < 0 { return Err(Error::Utterance { message: native_message() }); }
         Ok(frames)
     }
 
     pub fn end_utt(&self) -> Result<()> {
         let code = unsafe { bindings::ps_end_utt(self.raw) };
-        if code < 0 { return Err(Error); }
+        self.in_utt.set(false);
+        if code < 0 { return Err(Error::Utterance { message: native_message() }); }
         Ok(())
     }
 
-    pub fn get_hyp(&self) -> Option<(String, Option<String>, i32)> {
+    pub fn get_hyp(&self) -> Option<Hypothesis> {
         let mut score: i32 = 0;
         let mut c_utt_id: *const c_char = ptr::null();
         let c_hyp = unsafe { bindings::ps_get_hyp(self.raw, &mut score, &mut c_utt_id) };
         if c_hyp.is_null() { return None; }
 
-        let hyp = unsafe { CStr::from_ptr(c_hyp) }.to_string_lossy().into_owned();
+        // Strings are owned by decoder and may be changed by following calls.
+        let text = unsafe { CStr::from_ptr(c_hyp) }.to_string_lossy().into_owned();
         let utt_id = if c_utt_id.is_null() {
             None
         } else {
             Some(unsafe { CStr::from_ptr(c_utt_id) }.to_string_lossy().into_owned())
         };
-        Some((hyp, utt_id, score))
+        let prob = self.get_prob();
+        let n_frames = self.get_n_frames();
+        Some(Hypothesis {
+            text,
+            utt_id,
+            score,
+            prob,
+            confidence: if self.has_posteriors() {
+                Some(self.logmath().confidence(prob))
+            } else {
+                None
+            },
+            n_frames,
+            duration: frames_to_duration(n_frames, self.frame_rate()),
+            words: self.seg_iter().map(|seg| seg.to_word_segment()).collect(),
+        })
     }
 
     pub fn get_in_speech(&self) -> bool {
@@ -152,25 +478,68 @@ impl PsDecoder {
         unsafe { bindings::ps_get_n_frames(self.raw) }
     }
 
+    /// Number of frames per second, `-frate` argument.
+    pub fn frame_rate(&self) -> i32 {
+        match self.config.try_get_int("-frate") {
+            Ok(Some(frate)) if frate > 0 => frate as i32,
+            _ => DEFAULT_FRAME_RATE,
+        }
+    }
+
+    /// Sample rate audio is expected in, from `-samprate`.
+    pub fn sample_rate(&self) -> u32 {
+        match self.config.try_get_float("-samprate") {
+            Ok(Some(rate)) if rate > 0.0 => rate as u32,
+            _ => 16000,
+        }
+    }
+
     pub fn nbest(&self, start_frame: i32, end_frame: i32,
-                 ctx1: Option<&str>, ctx2: Option<&str>) -> NBestIter {
-        let c_ctx1 = ctx1.map(|s| CString::new(s).unwrap());
-        let c_ctx2 = ctx2.map(|s| CString::new(s).unwrap());
+                 ctx1: Option<&str>, ctx2: Option<&str>) -> Result<NBestIter> {
+        let c_ctx1 = ctx1.map(CString::new).transpose()?;
+        let c_ctx2 = ctx2.map(CString::new).transpose()?;
+        Ok(self.nbest_c(start_frame, end_frame, c_ctx1.as_ref(), c_ctx2.as_ref()))
+    }
+
+    pub fn nbest_simple(&self) -> NBestIter {
+        self.nbest_c(0, -1, None, None)
+    }
+
+    fn nbest_c(&self, start_frame: i32, end_frame: i32,
+               ctx1: Option<&CString>, ctx2: Option<&CString>) -> NBestIter<'_> {
         let raw_nbest = unsafe {
             bindings::ps_nbest(self.raw, start_frame, end_frame,
-                               c_ctx1.map_or(ptr::null(), |c| c.as_ptr()),
-                               c_ctx2.map_or(ptr::null(), |c| c.as_ptr()))
+                               ctx1.map_or(ptr::null(), |c| c.as_ptr()),
+                               ctx2.map_or(ptr::null(), |c| c.as_ptr()))
         };
-        NBestIter::new(raw_nbest)
+        NBestIter::with_frame_rate(raw_nbest, self.frame_rate())
     }
 
-    pub fn nbest_simple(&self) -> NBestIter {
-        self.nbest(0, -1, None, None)
+    // Whether hypothesis comes from lattice, so that its probabilities are
+    // posteriors. Only ngram and fsg searches build lattice, when `-bestpath`
+    // is enabled and utterance is finished.
+    pub(crate) fn has_posteriors(&self) -> bool {
+        if self.in_utt.get() || !matches!(self.config.try_get_boolean("-bestpath"), Ok(Some(true))) {
+            return false;
+        }
+        unsafe {
+            let search = bindings::ps_get_search(self.raw);
+            !search.is_null() && (!bindings::ps_get_lm(self.raw, search).is_null() ||
+                                  !bindings::ps_get_fsg(self.raw, search).is_null())
+        }
     }
 
+    /// Segments of current hypothesis, they have confidence when it comes
+    /// from lattice, see `Seg::confidence`.
     pub fn seg_iter(&self) -> SegIter {
         let mut best_score: i32 = 0;
-        SegIter::new(unsafe { bindings::ps_seg_iter(self.raw, &mut best_score) })
+        let raw_segs = unsafe { bindings::ps_seg_iter(self.raw, &mut best_score) };
+        let segs = SegIter::with_frame_rate(raw_segs, self.frame_rate());
+        if self.has_posteriors() {
+            segs.with_logmath(unsafe { ffi::ps_get_logmath(self.raw) })
+        } else {
+            segs
+        }
     }
 
     pub fn searches(&self) -> Searches {
@@ -178,9 +547,52 @@ impl PsDecoder {
     }
 }
 
+// Decoder doesn't depend on thread it was created in, so it may be moved
+// to another one. It is not `Sync`: native calls mutate decoder state even
+// for methods taking `&self`.
+unsafe impl Send for PsDecoder {}
+
 impl Drop for PsDecoder {
     fn drop(&mut self) {
-        let ref_count = unsafe { bindings::ps_free(self.raw) };
-        assert!(ref_count == 0);
+        // Decoder isn't retained anywhere else, so returned reference
+        // count is always zero.
+        unsafe { bindings::ps_free(self.raw) };
+    }
+}
+
+// Sphinx reports missing model files with obscure messages or doesn't
+// report them at all, so check them up front.
+// Sphinx closes current log when `-logfn` is set, which is captured pipe.
+fn release_redirected_log(config: &CmdLn) {
+    if config.path_value("-logfn").is_some() {
+        logging::release();
+    }
+}
+
+fn check_model_paths(config: &CmdLn) -> Result<()> {
+    let paths = [
+        ("-hmm", true),
+        ("-dict", false),
+        ("-fdict", false),
+        ("-lm", false),
+        ("-lmctl", false),
+        ("-jsgf", false),
+        ("-fsg", false),
+        ("-kws", false),
+        ("-allphone", false),
+        ("-mllr", false),
+    ];
+    for &(name, is_dir) in &paths {
+        if let Some(path) = config.path_value(name) {
+            let exists = if is_dir { path.is_dir() } else { path.is_file() };
+            if !exists {
+                return Err(Error::DecoderInit {
+                    message: format!("{} {} not found: {}",
+                                     &name[1..], if is_dir { "directory" } else { "file" },
+                                     path.display()),
+                });
+            }
+        }
     }
+    Ok(())
 }
@@ -0,0 +1,367 @@
+// Capturing of Sphinx log.
+//
+// Sphinx reports reasons of failures only to its log, so once capturing is
+// requested log is redirected into pipe which is read by background thread.
+// Thread forwards lines to selected destination and remembers last error
+// message so that it can be attached to `Error`. Without capturing log is
+// left untouched and errors carry only generic message.
+//
+// `err_set_callback` can't be used instead because callback is variadic.
+//
+// `E_FATAL` exits right after logging, so pipe is drained from `atexit`
+// handler, otherwise fatal message would be lost.
+
+use std;
+use std::ffi::CString;
+use std::fs::{File, OpenOptions};
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::os::unix::io::FromRawFd;
+use std::path::Path;
+use std::sync::{Arc, Condvar, Mutex, MutexGuard, OnceLock};
+use std::thread;
+use std::time::Duration;
+
+use libc;
+#[cfg(feature = "log")]
+use log;
+
+use ffi;
+
+// Marks lines written by `sync` to find out when reader thread has
+// processed everything logged before.
+const SYNC_MARK: u8 = 1;
+
+// Reader thread normally answers immediately, it only lags when sink blocks.
+const SYNC_TIMEOUT: Duration = Duration::from_millis(100);
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Level {
+    Debug,
+    Info,
+    Warn,
+    Error,
+    Fatal,
+}
+
+#[cfg(feature = "log")]
+impl Level {
+    fn to_log(self) -> log::Level {
+        match self {
+            Level::Debug => log::Level::Debug,
+            Level::Info => log::Level::Info,
+            Level::Warn => log::Level::Warn,
+            Level::Error | Level::Fatal => log::Level::Error,
+        }
+    }
+}
+
+struct State {
+    requested_sync: u64,
+    done_sync: u64,
+    last_error: Option<String>,
+}
+
+struct Shared {
+    state: Mutex<State>,
+    synced: Condvar,
+}
+
+struct Capture {
+    // Reset by `release` once Sphinx is about to close pipe, pointer must
+    // never be used after that.
+    fp: Mutex<Option<*mut libc::FILE>>,
+    shared: Arc<Shared>,
+}
+
+// Stdio streams are locked internally.
+unsafe impl Send for Capture {}
+unsafe impl Sync for Capture {}
+
+static CAPTURE: OnceLock<Option<Capture>> = OnceLock::new();
+
+enum Sink {
+    Stderr,
+    File(File),
+    Discard,
+    #[cfg(feature = "log")]
+    Log,
+}
+
+static SINK: Mutex<Sink> = Mutex::new(Sink::Stderr);
+
+fn set_sink(sink: Sink) {
+    *SINK.lock().unwrap_or_else(|e| e.into_inner()) = sink;
+}
+
+/// Captures PocketSphinx log, so that error messages are attached to
+/// `Error`s. Log is still written to stderr unless routed elsewhere.
+pub fn init_logging() {
+    CAPTURE.get_or_init(Capture::install);
+}
+
+/// Writes captured PocketSphinx log to stderr, this is the default.
+/// Doesn't start capturing by itself.
+pub fn log_to_stderr() {
+    set_sink(Sink::Stderr);
+}
+
+/// Appends PocketSphinx log to file, starts capturing.
+pub fn log_to_file<P: AsRef<Path>>(path: P) -> io::Result<()> {
+    let file = OpenOptions::new().create(true).append(true).open(path)?;
+    set_sink(Sink::File(file));
+    init_logging();
+    Ok(())
+}
+
+/// Discards PocketSphinx log, starts capturing, so error messages are
+/// still attached to `Error`s.
+pub fn silence_log() {
+    set_sink(Sink::Discard);
+    init_logging();
+}
+
+/// Routes PocketSphinx log to `log` crate with `pocketsphinx` target,
+/// starts capturing.
+#[cfg(feature = "log")]
+pub fn log_to_log_crate() {
+    set_sink(Sink::Log);
+    init_logging();
+}
+
+/// Returns last error message logged by Sphinx since previous call.
+pub(crate) fn last_error() -> Option<String> {
+    let capture = match CAPTURE.get() {
+        Some(Some(capture)) => capture,
+        _ => return None,
+    };
+    capture.sync().and_then(|mut state| state.last_error.take())
+}
+
+/// Stops using captured log for good, must be called before Sphinx replaces
+/// log with `-logfn`, which closes pipe.
+pub(crate) fn release() {
+    if let Some(Some(capture)) = CAPTURE.get() {
+        *capture.fp.lock().unwrap_or_else(|e| e.into_inner()) = None;
+    }
+}
+
+extern "C" fn drain_at_exit() {
+    if let Some(Some(capture)) = CAPTURE.get() {
+        capture.sync();
+    }
+}
+
+impl Capture {
+    fn install() -> Option<Capture> {
+        let mut fds = [0; 2];
+        if unsafe { libc::pipe(fds.as_mut_ptr()) } != 0 {
+            return None;
+        }
+        let fp = unsafe { libc::fdopen(fds[1], b"w\0".as_ptr() as *const libc::c_char) };
+        if fp.is_null() {
+            unsafe {
+                libc::close(fds[0]);
+                libc::close(fds[1]);
+            }
+            return None;
+        }
+        let reader = unsafe { File::from_raw_fd(fds[0]) };
+
+        let shared = Arc::new(Shared {
+            state: Mutex::new(State { requested_sync: 0, done_sync: 0, last_error: None }),
+            synced: Condvar::new(),
+        });
+        let thread_shared = shared.clone();
+        let spawned = thread::Builder::new()
+            .name("pocketsphinx-log".to_owned())
+            .spawn(move || read_log(reader, &thread_shared));
+        if spawned.is_err() {
+            unsafe { libc::fclose(fp) };
+            return None;
+        }
+
+        unsafe {
+            ffi::err_set_logfp(fp);
+            libc::atexit(drain_at_exit);
+        }
+        Some(Capture { fp: Mutex::new(Some(fp)), shared })
+    }
+
+    // Waits until reader thread processes everything logged so far.
+    // Returns `None` on timeout or if capture is released.
+    fn sync(&self) -> Option<MutexGuard<'_, State>> {
+        let token = {
+            let fp = self.fp.lock().unwrap_or_else(|e| e.into_inner());
+            let fp = (*fp)?;
+            let token = {
+                let mut state = self.shared.state.lock().unwrap_or_else(|e| e.into_inner());
+                state.requested_sync += 1;
+                state.requested_sync
+            };
+            // State lock must not be held while writing, reader thread may
+            // need it to free space in pipe.
+            let mark = CString::new(format!("{}{}\n", SYNC_MARK as char, token)).unwrap();
+            unsafe {
+                libc::fputs(mark.as_ptr(), fp);
+                libc::fflush(fp);
+            }
+            token
+        };
+
+        let state = self.shared.state.lock().unwrap_or_else(|e| e.into_inner());
+        let (state, result) = self.shared.synced
+            .wait_timeout_while(state, SYNC_TIMEOUT, |state| state.done_sync < token)
+            .unwrap_or_else(|e| e.into_inner());
+        if result.timed_out() { None } else { Some(state) }
+    }
+}
+
+fn read_log<R: Read>(reader: R, shared: &Shared) {
+    let mut level = Level::Info;
+    // Sync mark may be written in the middle of line which Sphinx hasn't
+    // finished yet, beginning of such line is kept here.
+    let mut pending = Vec::new();
+    for line in BufReader::new(reader).split(b'\n') {
+        let mut line = match line {
+            Ok(line) => line,
+            Err(_) => break,
+        };
+
+        if let Some(pos) = line.iter().position(|&b| b == SYNC_MARK) {
+            let token = String::from_utf8_lossy(&line[pos + 1..]).parse().unwrap_or(0);
+            pending.extend_from_slice(&line[..pos]);
+            let mut state = shared.state.lock().unwrap_or_else(|e| e.into_inner());
+            if token > state.done_sync {
+                state.done_sync = token;
+            }
+            shared.synced.notify_all();
+            continue;
+        }
+        if !pending.is_empty() {
+            pending.append(&mut line);
+            line = std::mem::take(&mut pending);
+        }
+
+        let line = String::from_utf8_lossy(&line);
+        // Lines without level continue previous message.
+        let message = match parse_line(&line) {
+            Some((line_level, message)) => {
+                level = line_level;
+                message
+            }
+            None => &line,
+        };
+        if level == Level::Error || level == Level::Fatal {
+            let mut state = shared.state.lock().unwrap_or_else(|e| e.into_inner());
+            state.last_error = Some(message.trim().to_owned());
+        }
+
+        match *SINK.lock().unwrap_or_else(|e| e.into_inner()) {
+            Sink::Stderr => { let _ = writeln!(io::stderr(), "{}", line); }
+            Sink::File(ref mut file) => { let _ = writeln!(file, "{}", line); }
+            Sink::Discard => {}
+            #[cfg(feature = "log")]
+            Sink::Log => log::log!(target: "pocketsphinx", level.to_log(), "{}", message),
+        }
+    }
+}
+
+// Splits line like `ERROR: "file.c", line 10: message` into level and message.
+fn parse_line(line: &str) -> Option<(Level, &str)> {
+    let levels = [
+        ("DEBUG: ", Level::Debug),
+        ("INFO: ", Level::Info),
+        ("WARN: ", Level::Warn),
+        ("WARNING: ", Level::Warn),
+        ("ERROR: ", Level::Error),
+        ("SYSTEM_ERROR: ", Level::Error),
+        ("FATAL: ", Level::Fatal),
+        ("FATAL_ERROR: ", Level::Fatal),
+    ];
+    let (level, rest) = levels.iter()
+        .find(|&&(prefix, _)| line.starts_with(prefix))
+        .map(|&(prefix, level)| (level, &line[prefix.len()..]))?;
+    Some((level, strip_location(rest)))
+}
+
+// Strips source location, which is either `"file.c", line 10: ` or `file.c(10): `.
+fn strip_location(message: &str) -> &str {
+    let end = if message.starts_with('"') {
+        message.find(", line ").and_then(|pos| message[pos..].find(": ").map(|end| pos + end))
+    } else {
+        message.find("): ").filter(|&pos| {
+            message[..pos].rfind('(').is_some_and(|open| {
+                message[open + 1..pos].bytes().all(|b| b.is_ascii_digit())
+            })
+        }).map(|pos| pos + 1)
+    };
+    match end {
+        Some(end) => &message[end + 2..],
+        None => message,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn read(log: &[u8]) -> State {
+        set_sink(Sink::Discard);
+        let shared = Shared {
+            state: Mutex::new(State { requested_sync: 0, done_sync: 0, last_error: None }),
+            synced: Condvar::new(),
+        };
+        read_log(log, &shared);
+        shared.state.into_inner().unwrap()
+    }
+
+    #[test]
+    fn levels() {
+        assert_eq!(parse_line("INFO: loaded"), Some((Level::Info, "loaded")));
+        assert_eq!(parse_line("WARNING: odd"), Some((Level::Warn, "odd")));
+        assert_eq!(parse_line("SYSTEM_ERROR: no file"), Some((Level::Error, "no file")));
+        assert_eq!(parse_line("FATAL_ERROR: abort"), Some((Level::Fatal, "abort")));
+        assert_eq!(parse_line("loaded"), None);
+        assert_eq!(parse_line("INFO:no space"), None);
+    }
+
+    #[test]
+    fn quoted_location() {
+        assert_eq!(parse_line("ERROR: \"ngram_search.c\", line 208: Failed to read"),
+                   Some((Level::Error, "Failed to read")));
+        assert_eq!(strip_location("\"a.c\", line 1: x: y"), "x: y");
+    }
+
+    #[test]
+    fn parenthesized_location() {
+        assert_eq!(parse_line("INFO: cmn.c(133): CMN: 40.00"), Some((Level::Info, "CMN: 40.00")));
+        assert_eq!(strip_location("fsg(a): b"), "fsg(a): b");
+    }
+
+    #[test]
+    fn no_location() {
+        assert_eq!(strip_location("Failed to read"), "Failed to read");
+        assert_eq!(strip_location("\"quoted\" word"), "\"quoted\" word");
+    }
+
+    #[test]
+    fn continuation_keeps_level() {
+        assert_eq!(read(b"ERROR: x.c(3): failed\n  details\n").last_error, Some("details".to_owned()));
+        assert_eq!(read(b"INFO: loaded\n  details\n").last_error, None);
+        assert_eq!(read(b"ERROR: failed\nINFO: details\n").last_error, Some("failed".to_owned()));
+    }
+
+    #[test]
+    fn sync_mark_in_middle_of_line() {
+        let state = read(b"ERROR: \"a.c\", line 1: broken\x012\n message\nINFO: done\n");
+        assert_eq!(state.done_sync, 2);
+        assert_eq!(state.last_error, Some("broken message".to_owned()));
+    }
+
+    #[test]
+    fn sync_mark_on_own_line() {
+        let state = read(b"ERROR: failed\n\x013\n\x011\n");
+        assert_eq!(state.done_sync, 3);
+        assert_eq!(state.last_error, Some("failed".to_owned()));
+    }
+}
@@ -0,0 +1,64 @@
+use std;
+
+use ffi;
+use super::PsDecoder;
+
+/// Decoder's log-domain arithmetic.
+///
+/// Scores and probabilities are integer logarithms with base close to 1,
+/// `-logbase` argument.
+pub struct LogMath<'a> {
+    raw: *mut ffi::logmath_t,
+    _marker: std::marker::PhantomData<&'a PsDecoder>,
+}
+
+impl PsDecoder {
+    pub fn logmath(&self) -> LogMath<'_> {
+        LogMath { raw: unsafe { ffi::ps_get_logmath(self.raw) }, _marker: std::marker::PhantomData }
+    }
+}
+
+impl<'a> LogMath<'a> {
+    pub fn base(&self) -> f64 {
+        unsafe { ffi::logmath_get_base(self.raw) }
+    }
+
+    /// Logarithm of zero probability.
+    pub fn zero(&self) -> i32 {
+        unsafe { ffi::logmath_get_zero(self.raw) }
+    }
+
+    /// Adds two probabilities given as logarithms.
+    pub fn add(&self, logb_p: i32, logb_q: i32) -> i32 {
+        unsafe { ffi::logmath_add(self.raw, logb_p, logb_q) }
+    }
+
+    pub fn log(&self, p: f64) -> i32 {
+        unsafe { ffi::logmath_log(self.raw, p) }
+    }
+
+    pub fn exp(&self, logb_p: i32) -> f64 {
+        unsafe { ffi::logmath_exp(self.raw, logb_p) }
+    }
+
+    pub fn ln_to_log(&self, log_p: f64) -> i32 {
+        unsafe { ffi::logmath_ln_to_log(self.raw, log_p) }
+    }
+
+    pub fn log_to_ln(&self, logb_p: i32) -> f64 {
+        unsafe { ffi::logmath_log_to_ln(self.raw, logb_p) }
+    }
+
+    pub fn log10_to_log(&self, log_p: f64) -> i32 {
+        unsafe { ffi::logmath_log10_to_log(self.raw, log_p) }
+    }
+
+    pub fn log_to_log10(&self, logb_p: i32) -> f64 {
+        unsafe { ffi::logmath_log_to_log10(self.raw, logb_p) }
+    }
+
+    /// Converts log posterior probability into value in [0, 1].
+    pub fn confidence(&self, logb_p: i32) -> f64 {
+        self.exp(logb_p).clamp(0.0, 1.0)
+    }
+}
@@ -1,8 +1,17 @@
 use bindings;
 
 use std;
+use std::borrow::Cow;
 use std::ffi::CStr;
+use std::time::Duration;
 
+use ffi;
+use super::{NBestHypothesis, PsDecoder, WordSegment, DEFAULT_FRAME_RATE, frames_to_duration, split_variant};
+
+// How many n-best entries are looked through per requested distinct hypothesis.
+const NBEST_SCAN_FACTOR: usize = 10;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub struct SegProbs {
     pub prob: i32,
     pub ascr: i32,
@@ -12,12 +21,15 @@ pub struct SegProbs {
 
 pub struct Seg<'a> {
     raw: *const bindings::ps_seg_t,
+    frate: i32,
+    // Null when segment probability isn't posterior.
+    lmath: *mut ffi::logmath_t,
     _marker: std::marker::PhantomData<&'a str>,
 }
 
 impl<'a> Seg<'a> {
-    fn new(raw: *const bindings::ps_seg_t) -> Self {
-        Seg { raw: raw, _marker: std::marker::PhantomData }
+    fn new(raw: *const bindings::ps_seg_t, frate: i32, lmath: *mut ffi::logmath_t) -> Self {
+        Seg { raw, frate, lmath, _marker: std::marker::PhantomData }
     }
 
     pub fn prob(&self) -> SegProbs {
@@ -27,9 +39,9 @@ impl<'a> Seg<'a> {
         probs
     }
 
-    pub fn word(&self) -> &'a str {
+    pub fn word(&self) -> Cow<'a, str> {
         let c_word = unsafe { bindings::ps_seg_word(self.raw) };
-        unsafe { CStr::from_ptr(c_word) }.to_str().unwrap()
+        unsafe { CStr::from_ptr(c_word) }.to_string_lossy()
     }
 
     pub fn frames(&self) -> (i32, i32) {
@@ -38,16 +50,63 @@ impl<'a> Seg<'a> {
         unsafe { bindings::ps_seg_frames(self.raw, &mut sf, &mut ef) }
         (sf, ef)
     }
+
+    pub fn start_time(&self) -> Duration {
+        frames_to_duration(self.frames().0, self.frate)
+    }
+
+    /// End frame is included into segment, so it is time when it ends.
+    pub fn end_time(&self) -> Duration {
+        frames_to_duration(self.frames().1 + 1, self.frate)
+    }
+
+    /// Word posterior probability in [0, 1], available only for best
+    /// hypothesis segments of ngram or fsg search when `-bestpath` is
+    /// enabled and utterance is finished.
+    pub fn confidence(&self) -> Option<f64> {
+        if self.lmath.is_null() {
+            None
+        } else {
+            Some(unsafe { ffi::logmath_exp(self.lmath, self.prob().prob) }.clamp(0.0, 1.0))
+        }
+    }
+
+    pub fn to_word_segment(&self) -> WordSegment {
+        let (start_frame, end_frame) = self.frames();
+        WordSegment {
+            word: self.word().into_owned(),
+            start_frame,
+            end_frame,
+            start_time: self.start_time(),
+            end_time: self.end_time(),
+            probs: self.prob(),
+            confidence: self.confidence(),
+        }
+    }
 }
 
 pub struct SegIter<'a> {
     raw: *mut bindings::ps_seg_t,
+    frate: i32,
+    lmath: *mut ffi::logmath_t,
     _marker: std::marker::PhantomData<&'a str>,
 }
 
 impl<'a> SegIter<'a> {
+    /// Segment times assume default frame rate of 100 frames per second.
     pub fn new(raw: *mut bindings::ps_seg_t) -> Self {
-        SegIter { raw: raw, _marker: std::marker::PhantomData }
+        SegIter::with_frame_rate(raw, DEFAULT_FRAME_RATE)
+    }
+
+    // `frate` is decoder's frame rate used to convert frames into time.
+    pub(crate) fn with_frame_rate(raw: *mut bindings::ps_seg_t, frate: i32) -> Self {
+        SegIter { raw, frate, lmath: std::ptr::null_mut(), _marker: std::marker::PhantomData }
+    }
+
+    // Segment probabilities are posteriors, they are converted with `lmath`.
+    pub(crate) fn with_logmath(mut self, lmath: *mut ffi::logmath_t) -> Self {
+        self.lmath = lmath;
+        self
     }
 }
 
@@ -66,7 +125,7 @@ impl<'a> Iterator for SegIter<'a> {
         if self.raw.is_null() {
             None
         } else {
-            let item = Seg::new(self.raw);
+            let item = Seg::new(self.raw, self.frate, self.lmath);
             self.raw = unsafe { bindings::ps_seg_next(self.raw) };
             Some(item)
         }
@@ -75,39 +134,47 @@ impl<'a> Iterator for SegIter<'a> {
 
 pub struct NBest<'a> {
     raw: *const bindings::ps_nbest_t,
+    frate: i32,
     _marker: std::marker::PhantomData<&'a str>,
 }
 
 impl<'a> NBest<'a> {
-    fn new(raw: *const bindings::ps_nbest_t) -> Self {
-        NBest { raw: raw, _marker: std::marker::PhantomData }
+    fn new(raw: *const bindings::ps_nbest_t, frate: i32) -> Self {
+        NBest { raw, frate, _marker: std::marker::PhantomData }
     }
 
-    pub fn hyp(&self) -> (&'a str, i32) {
+    pub fn hyp(&self) -> (Cow<'a, str>, i32) {
         let mut score: i32 = 0;
         let c_hyp = unsafe { bindings::ps_nbest_hyp(self.raw, &mut score) };
         if c_hyp.is_null() {
-            ("", 0)
+            (Cow::Borrowed(""), 0)
         } else {
-            (unsafe { CStr::from_ptr(c_hyp) }.to_str().unwrap(), score)
+            (unsafe { CStr::from_ptr(c_hyp) }.to_string_lossy(), score)
         }
     }
 
     pub fn segments(&self) -> (SegIter<'a>, i32) {
         let mut score: i32 = 0;
         let seg_raw = unsafe { bindings::ps_nbest_seg(self.raw, &mut score) };
-        (SegIter::new(seg_raw), score)
+        (SegIter::with_frame_rate(seg_raw, self.frate), score)
     }
 }
 
 pub struct NBestIter<'a> {
     raw: *mut bindings::ps_nbest_t,
+    frate: i32,
     _marker: std::marker::PhantomData<&'a str>,
 }
 
 impl<'a> NBestIter<'a> {
+    /// Segment times assume default frame rate of 100 frames per second.
     pub fn new(raw: *mut bindings::ps_nbest_t) -> Self {
-        NBestIter { raw: raw, _marker: std::marker::PhantomData }
+        NBestIter::with_frame_rate(raw, DEFAULT_FRAME_RATE)
+    }
+
+    // `frate` is decoder's frame rate used to convert frames into time.
+    pub(crate) fn with_frame_rate(raw: *mut bindings::ps_nbest_t, frate: i32) -> Self {
+        NBestIter { raw, frate, _marker: std::marker::PhantomData }
     }
 }
 
@@ -126,9 +193,144 @@ impl<'a> Iterator for NBestIter<'a> {
         if self.raw.is_null() {
             None
         } else {
-            let item = NBest::new(self.raw);
+            let item = NBest::new(self.raw, self.frate);
             self.raw = unsafe { bindings::ps_nbest_next(self.raw) };
             Some(item)
         }
     }
 }
+
+// Fillers are silence and noise: `<sil>`, `[NOISE]`, `++BREATH++`.
+fn is_filler(word: &str) -> bool {
+    let word = split_variant(word).0;
+    (word.starts_with('<') && word.ends_with('>')) ||
+        (word.starts_with('[') && word.ends_with(']')) ||
+        (word.len() > 4 && word.starts_with("++") && word.ends_with("++"))
+}
+
+impl PsDecoder {
+    /// Returns up to `n` best distinct hypotheses without fillers in words.
+    pub fn nbest_list(&self, n: usize) -> Vec<NBestHypothesis> {
+        self.nbest_list_with(n, true, true)
+    }
+
+    /// Returns up to `n` best hypotheses, optionally stripping fillers from
+    /// words and merging hypotheses with the same text.
+    ///
+    /// Posteriors are computed from path scores scaled down by `-ascale`, like
+    /// lattice posteriors. Posterior of merged hypothesis includes its duplicates.
+    pub fn nbest_list_with(&self, n: usize, strip_fillers: bool, dedup: bool) -> Vec<NBestHypothesis> {
+        let lmath = self.logmath();
+        let ascale = match self.config.try_get_float("-ascale") {
+            Ok(Some(ascale)) if ascale > 0.0 => ascale,
+            _ => 20.0,
+        };
+        let limit = if dedup { n.saturating_mul(NBEST_SCAN_FACTOR) } else { n };
+
+        let mut list: Vec<NBestHypothesis> = Vec::new();
+        // Scaled log probabilities of each hypothesis in `list` and its duplicates.
+        let mut masses: Vec<Vec<i32>> = Vec::new();
+        for nbest in self.nbest_simple().take(limit) {
+            let (text, score) = nbest.hyp();
+            let text = text.trim();
+            let mass = (score as f64 / ascale) as i32;
+            if dedup {
+                if let Some(index) = list.iter().position(|hyp| hyp.text == text) {
+                    masses[index].push(mass);
+                    continue;
+                }
+                // Keep looking for duplicates of already found hypotheses.
+                if list.len() == n {
+                    continue;
+                }
+            }
+            let words = nbest.segments().0
+                .map(|seg| seg.to_word_segment())
+                .filter(|word| !strip_fillers || !is_filler(&word.word))
+                .collect();
+            list.push(NBestHypothesis { text: text.to_owned(), score, posterior: 0.0, words });
+            masses.push(vec![mass]);
+        }
+
+        let posteriors = posteriors(&masses, lmath.zero(), |a, b| lmath.add(a, b), |a| lmath.exp(a));
+        for (hyp, posterior) in list.iter_mut().zip(posteriors) {
+            hyp.posterior = posterior;
+        }
+        list
+    }
+}
+
+// Normalizes log probabilities of hypotheses, each given with its
+// duplicates, so that they sum up to 1. `add` and `exp` work in log domain
+// where `zero` is logarithm of zero.
+fn posteriors<A, E>(masses: &[Vec<i32>], zero: i32, add: A, exp: E) -> Vec<f64>
+    where A: Fn(i32, i32) -> i32, E: Fn(i32) -> f64
+{
+    let sum = |masses: &[i32]| masses.iter().fold(zero, |sum, &mass| add(sum, mass));
+    let merged: Vec<i32> = masses.iter().map(|masses| sum(masses)).collect();
+    let total = sum(&merged);
+    merged.iter().map(|&mass| exp(mass - total).clamp(0.0, 1.0)).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Log domain with base close to 1, like Sphinx's one.
+    const BASE: f64 = 1.0001;
+    const ZERO: i32 = -0x3fff_ffff;
+
+    fn log(p: f64) -> i32 {
+        if p == 0.0 { ZERO } else { (p.ln() / BASE.ln()).round() as i32 }
+    }
+
+    fn add(a: i32, b: i32) -> i32 {
+        log(exp(a) + exp(b))
+    }
+
+    fn exp(a: i32) -> f64 {
+        if a <= ZERO { 0.0 } else { BASE.powi(a) }
+    }
+
+    fn close(a: f64, b: f64) -> bool {
+        (a - b).abs() < 1e-3
+    }
+
+    #[test]
+    fn fillers() {
+        assert!(is_filler("<sil>"));
+        assert!(is_filler("</s>"));
+        assert!(is_filler("[NOISE]"));
+        assert!(is_filler("++BREATH++"));
+        assert!(is_filler("<sil>(2)"));
+        assert!(!is_filler("++"));
+        assert!(!is_filler("++++"));
+        assert!(!is_filler("read(2)"));
+        assert!(!is_filler("read"));
+        assert!(!is_filler(""));
+    }
+
+    #[test]
+    fn posteriors_sum_up_to_one() {
+        // Scores are tiny probabilities, only their ratio matters.
+        let masses = vec![vec![log(0.002)], vec![log(0.006)], vec![log(0.002)]];
+        let posteriors = posteriors(&masses, ZERO, add, exp);
+        assert!(close(posteriors[0], 0.2) && close(posteriors[1], 0.6) && close(posteriors[2], 0.2),
+                "{:?}", posteriors);
+    }
+
+    #[test]
+    fn merged_duplicates_add_up() {
+        let separate = posteriors(&[vec![log(0.5)], vec![log(0.3)], vec![log(0.2)]], ZERO, add, exp);
+        let merged = posteriors(&[vec![log(0.5)], vec![log(0.3), log(0.2)]], ZERO, add, exp);
+        assert_eq!(merged.len(), 2);
+        assert!(close(merged[0], separate[0]), "{:?}", merged);
+        assert!(close(merged[1], separate[1] + separate[2]), "{:?}", merged);
+        assert!(close(merged[1], 0.5), "{:?}", merged);
+    }
+
+    #[test]
+    fn empty_list() {
+        assert!(posteriors(&[], ZERO, add, exp).is_empty());
+    }
+}
@@ -0,0 +1,82 @@
+use std::ops::{Deref, DerefMut};
+use std::sync::{Condvar, Mutex, MutexGuard};
+
+use super::{Config, Error, PsDecoder, Result};
+
+/// Set of identically configured decoders for concurrent processing.
+///
+/// Each decoder loads its own copy of models, PocketSphinx has no public
+/// API for sharing acoustic model between decoders.
+pub struct DecoderPool {
+    idle: Mutex<Vec<PsDecoder>>,
+    returned: Condvar,
+    size: usize,
+}
+
+impl DecoderPool {
+    pub fn new(config: &Config, size: usize) -> Result<Self> {
+        // Lease from empty pool would wait forever.
+        if size == 0 {
+            return Err(Error::DecoderInit { message: "pool must contain at least one decoder".to_owned() });
+        }
+        let decoders = (0..size)
+            .map(|_| config.to_cmdln().and_then(PsDecoder::new))
+            .collect::<Result<Vec<_>>>()?;
+        Ok(DecoderPool { idle: Mutex::new(decoders), returned: Condvar::new(), size })
+    }
+
+    pub fn size(&self) -> usize {
+        self.size
+    }
+
+    /// Takes decoder from pool, waits until one is returned if all are in use.
+    pub fn lease(&self) -> Lease<'_> {
+        let mut idle = self.lock();
+        loop {
+            if let Some(decoder) = idle.pop() {
+                return Lease { pool: self, decoder: Some(decoder) };
+            }
+            idle = self.returned.wait(idle).unwrap_or_else(|e| e.into_inner());
+        }
+    }
+
+    /// Takes decoder from pool if there is idle one.
+    pub fn try_lease(&self) -> Option<Lease<'_>> {
+        self.lock().pop().map(|decoder| Lease { pool: self, decoder: Some(decoder) })
+    }
+
+    fn lock(&self) -> MutexGuard<'_, Vec<PsDecoder>> {
+        // Decoders are only pushed and popped under lock, so vector is
+        // consistent even if some thread panicked.
+        self.idle.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+/// Decoder taken from pool, it is returned back on drop.
+pub struct Lease<'a> {
+    pool: &'a DecoderPool,
+    decoder: Option<PsDecoder>,
+}
+
+impl<'a> Deref for Lease<'a> {
+    type Target = PsDecoder;
+
+    fn deref(&self) -> &PsDecoder {
+        self.decoder.as_ref().unwrap()
+    }
+}
+
+impl<'a> DerefMut for Lease<'a> {
+    fn deref_mut(&mut self) -> &mut PsDecoder {
+        self.decoder.as_mut().unwrap()
+    }
+}
+
+impl<'a> Drop for Lease<'a> {
+    fn drop(&mut self) {
+        if let Some(decoder) = self.decoder.take() {
+            self.pool.lock().push(decoder);
+            self.pool.returned.notify_one();
+        }
+    }
+}
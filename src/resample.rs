@@ -0,0 +1,188 @@
+// Polyphase windowed-sinc resampler.
+//
+// Rates are reduced to `up / down` ratio, output sample `n` lies at input
+// position `n * down / up`. Filter coefficients are precomputed for each of
+// `up` possible fractional positions, so uncommon rates with large `up`
+// cost more memory, not more time.
+
+use std::f64::consts::PI;
+
+use super::{Error, Result};
+
+// Number of sinc zero crossings on each side of filter at cutoff frequency.
+const ZERO_CROSSINGS: usize = 16;
+// Cutoff relative to lower Nyquist frequency, leaves room for transition band.
+const ROLLOFF: f64 = 0.95;
+
+/// Converts 16-bit mono audio between sample rates, keeps state between
+/// calls so that audio may be fed in blocks of any size.
+pub struct Resampler {
+    input_rate: u32,
+    output_rate: u32,
+    up: usize,
+    down: usize,
+    half: usize,
+    // `up` rows of `2 * half` coefficients.
+    coeffs: Vec<f32>,
+    buffer: Vec<f32>,
+    pos: usize,
+    phase: usize,
+}
+
+fn gcd(a: u32, b: u32) -> u32 {
+    if b == 0 { a } else { gcd(b, a % b) }
+}
+
+fn sinc(x: f64) -> f64 {
+    if x == 0.0 { 1.0 } else { (PI * x).sin() / (PI * x) }
+}
+
+fn blackman(x: f64, half: f64) -> f64 {
+    if x.abs() >= half {
+        0.0
+    } else {
+        0.42 + 0.5 * (PI * x / half).cos() + 0.08 * (2.0 * PI * x / half).cos()
+    }
+}
+
+impl Resampler {
+    pub fn new(input_rate: u32, output_rate: u32) -> Result<Self> {
+        if input_rate == 0 || output_rate == 0 {
+            return Err(Error::Audio(format!("invalid resampling from {} Hz to {} Hz",
+                                            input_rate, output_rate)));
+        }
+        let divisor = gcd(input_rate, output_rate);
+        let up = (output_rate / divisor) as usize;
+        let down = (input_rate / divisor) as usize;
+        let cutoff = ROLLOFF * (output_rate as f64 / input_rate as f64).min(1.0);
+        let half = (ZERO_CROSSINGS as f64 / cutoff).ceil() as usize;
+
+        let mut coeffs = Vec::with_capacity(up * 2 * half);
+        for phase in 0..up {
+            let offset = phase as f64 / up as f64;
+            let start = coeffs.len();
+            for tap in 0..2 * half {
+                let x = tap as f64 - (half - 1) as f64 - offset;
+                coeffs.push((cutoff * sinc(cutoff * x) * blackman(x, half as f64)) as f32);
+            }
+            // Normalize so that constant signal keeps its level at every phase.
+            let sum: f32 = coeffs[start..].iter().sum();
+            for coeff in &mut coeffs[start..] {
+                *coeff /= sum;
+            }
+        }
+
+        Ok(Resampler {
+            input_rate,
+            output_rate,
+            up,
+            down,
+            half,
+            coeffs,
+            buffer: vec![0.0; half],
+            pos: half,
+            phase: 0,
+        })
+    }
+
+    pub fn input_rate(&self) -> u32 {
+        self.input_rate
+    }
+
+    pub fn output_rate(&self) -> u32 {
+        self.output_rate
+    }
+
+    /// Resamples next block of input, appends available samples to `output`.
+    ///
+    /// Output lags behind input by filter length, use `flush` to get the rest.
+    pub fn process(&mut self, input: &[i16], output: &mut Vec<i16>) {
+        self.buffer.extend(input.iter().map(|&sample| sample as f32));
+        self.drain(output);
+    }
+
+    /// Pads input with silence to get out remaining samples and resets state.
+    pub fn flush(&mut self, output: &mut Vec<i16>) {
+        let padded = self.buffer.len() + self.half;
+        self.buffer.resize(padded, 0.0);
+        self.drain(output);
+        self.buffer.clear();
+        self.buffer.resize(self.half, 0.0);
+        self.pos = self.half;
+        self.phase = 0;
+    }
+
+    fn drain(&mut self, output: &mut Vec<i16>) {
+        let taps = 2 * self.half;
+        while self.pos + self.half < self.buffer.len() {
+            let window = &self.buffer[self.pos + 1 - self.half..self.pos + 1 + self.half];
+            let coeffs = &self.coeffs[self.phase * taps..(self.phase + 1) * taps];
+            let sample: f32 = window.iter().zip(coeffs).map(|(s, c)| s * c).sum();
+            output.push(sample.round().clamp(i16::MIN as f32, i16::MAX as f32) as i16);
+
+            self.phase += self.down;
+            self.pos += self.phase / self.up;
+            self.phase %= self.up;
+        }
+        let consumed = (self.pos + 1 - self.half).min(self.buffer.len());
+        self.buffer.drain(..consumed);
+        self.pos -= consumed;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn resample(input_rate: u32, output_rate: u32, input: &[i16], block: usize) -> Vec<i16> {
+        let mut resampler = Resampler::new(input_rate, output_rate).unwrap();
+        let mut output = Vec::new();
+        for chunk in input.chunks(block) {
+            resampler.process(chunk, &mut output);
+        }
+        resampler.flush(&mut output);
+        output
+    }
+
+    #[test]
+    fn output_length_follows_rate_ratio() {
+        for &(input_rate, output_rate) in &[(48000, 16000), (44100, 16000), (8000, 16000), (22050, 16000)] {
+            let input = vec![0; input_rate as usize];
+            let output = resample(input_rate, output_rate, &input, 1000);
+            assert!((output.len() as i64 - output_rate as i64).abs() <= 1,
+                    "{} -> {}: {} samples", input_rate, output_rate, output.len());
+        }
+    }
+
+    #[test]
+    fn constant_signal_keeps_level() {
+        let input = vec![1000; 44100];
+        let output = resample(44100, 16000, &input, 441);
+        // Edges are affected by silence padding.
+        for &sample in &output[100..output.len() - 100] {
+            assert!((sample - 1000).abs() <= 1, "sample {}", sample);
+        }
+    }
+
+    #[test]
+    fn output_does_not_depend_on_block_size() {
+        let input: Vec<i16> = (0..4800).map(|i| ((i * 37) % 2000 - 1000) as i16).collect();
+        assert_eq!(resample(48000, 16000, &input, 7), resample(48000, 16000, &input, 4800));
+    }
+
+    #[test]
+    fn sine_is_preserved() {
+        let sine = |rate: u32, i: usize| 10000.0 * (2.0 * PI * 440.0 * i as f64 / rate as f64).sin();
+        let input: Vec<i16> = (0..48000).map(|i| sine(48000, i) as i16).collect();
+        let output = resample(48000, 16000, &input, 512);
+        for (i, &sample) in output.iter().enumerate().skip(1000).take(14000) {
+            assert!((sample as f64 - sine(16000, i)).abs() < 5.0, "sample {}: {}", i, sample);
+        }
+    }
+
+    #[test]
+    fn zero_rate_is_rejected() {
+        assert!(Resampler::new(0, 16000).is_err());
+        assert!(Resampler::new(16000, 0).is_err());
+    }
+}
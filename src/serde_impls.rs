@@ -0,0 +1,142 @@
+use std::fmt;
+
+use serde::de::{self, Deserialize, Deserializer, MapAccess, SeqAccess, Visitor};
+use serde::ser::{self, Serialize, SerializeMap, Serializer};
+
+use args::{self, ArgType, ArgValue};
+use super::{CmdLn, Config};
+
+// Arguments are (de)serialized as map from argument name without leading
+// dash to value typed according to argument definition.
+
+impl Serialize for ArgValue {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match *self {
+            ArgValue::Integer(value) => serializer.serialize_i64(value),
+            ArgValue::Floating(value) => serializer.serialize_f64(value),
+            ArgValue::Boolean(value) => serializer.serialize_bool(value),
+            ArgValue::String(Some(ref value)) => serializer.serialize_str(value),
+            ArgValue::String(None) => serializer.serialize_none(),
+            ArgValue::StringList(ref values) => values.serialize(serializer),
+        }
+    }
+}
+
+// Converts raw value to typed one, values which can't be parsed are kept
+// as strings so that they are reported when `CmdLn` is built.
+fn typed_value(name: &str, value: &str) -> ArgValue {
+    let typed = match args::arg_type(name) {
+        Some(ArgType::Integer) => args::parse_int(value).map(ArgValue::Integer),
+        Some(ArgType::Floating) => args::parse_float(value).map(ArgValue::Floating),
+        Some(ArgType::Boolean) => args::parse_boolean(value).map(ArgValue::Boolean),
+        Some(ArgType::StringList) =>
+            Some(ArgValue::StringList(value.split(',').map(str::to_owned).collect())),
+        Some(ArgType::String) | None => None,
+    };
+    typed.unwrap_or_else(|| ArgValue::String(Some(value.to_owned())))
+}
+
+impl Serialize for Config {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut map = serializer.serialize_map(None)?;
+        for (name, value) in self.args() {
+            let value = value.to_str().ok_or_else(|| {
+                ser::Error::custom(format!("value of {} is not valid UTF-8", name))
+            })?;
+            map.serialize_entry(&name[1..], &typed_value(name, value))?;
+        }
+        map.end()
+    }
+}
+
+impl Serialize for CmdLn {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let values = self.dump().map_err(ser::Error::custom)?;
+        let mut map = serializer.serialize_map(None)?;
+        for (name, value) in values {
+            // Unset strings are skipped, not all formats support null.
+            if value != ArgValue::String(None) {
+                map.serialize_entry(&name[1..], &value)?;
+            }
+        }
+        map.end()
+    }
+}
+
+// Argument value in command-line form.
+struct RawValue(String);
+
+impl<'de> Deserialize<'de> for RawValue {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_any(RawValueVisitor)
+    }
+}
+
+struct RawValueVisitor;
+
+impl<'de> Visitor<'de> for RawValueVisitor {
+    type Value = RawValue;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("argument value")
+    }
+
+    fn visit_bool<E: de::Error>(self, value: bool) -> Result<RawValue, E> {
+        Ok(RawValue(if value { "yes" } else { "no" }.to_owned()))
+    }
+
+    fn visit_i64<E: de::Error>(self, value: i64) -> Result<RawValue, E> {
+        Ok(RawValue(value.to_string()))
+    }
+
+    fn visit_u64<E: de::Error>(self, value: u64) -> Result<RawValue, E> {
+        Ok(RawValue(value.to_string()))
+    }
+
+    fn visit_f64<E: de::Error>(self, value: f64) -> Result<RawValue, E> {
+        Ok(RawValue(value.to_string()))
+    }
+
+    fn visit_str<E: de::Error>(self, value: &str) -> Result<RawValue, E> {
+        Ok(RawValue(value.to_owned()))
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<RawValue, A::Error> {
+        let mut items = Vec::new();
+        while let Some(item) = seq.next_element::<String>()? {
+            items.push(item);
+        }
+        Ok(RawValue(items.join(",")))
+    }
+}
+
+struct ConfigVisitor;
+
+impl<'de> Visitor<'de> for ConfigVisitor {
+    type Value = Config;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("map of decoder arguments")
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Config, A::Error> {
+        let mut config = Config::new();
+        while let Some((name, value)) = map.next_entry::<String, RawValue>()? {
+            config = config.arg(&name, value.0);
+        }
+        Ok(config)
+    }
+}
+
+impl<'de> Deserialize<'de> for Config {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_map(ConfigVisitor)
+    }
+}
+
+impl<'de> Deserialize<'de> for CmdLn {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let config = Config::deserialize(deserializer)?;
+        config.to_cmdln().map_err(de::Error::custom)
+    }
+}
@@ -0,0 +1,449 @@
+// HTK Standard Lattice Format (SLF) and Graphviz export.
+//
+// Native lattice is converted into owned `SlfLattice` which is then written,
+// so lattices read from files can be written back the same way.
+
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{BufRead, BufReader, Write};
+use std::path::Path;
+
+use super::{Error, Lattice, Result};
+
+/// Lattice in HTK Standard Lattice Format, doesn't need decoder.
+///
+/// Scores are natural logarithms, as in SLF files.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct SlfLattice {
+    /// Header fields like `VERSION` or `lmscale` in order of appearance.
+    pub header: Vec<(String, String)>,
+    pub nodes: Vec<SlfNode>,
+    pub links: Vec<SlfLink>,
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct SlfNode {
+    /// Time in seconds.
+    pub time: Option<f64>,
+    pub word: Option<String>,
+    /// Pronunciation variant, starting from 1.
+    pub variant: Option<u32>,
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct SlfLink {
+    /// Index of source node.
+    pub start: usize,
+    /// Index of destination node.
+    pub end: usize,
+    pub word: Option<String>,
+    pub acoustic: Option<f64>,
+    pub language: Option<f64>,
+    pub posterior: Option<f64>,
+}
+
+impl<'a> Lattice<'a> {
+    /// Converts lattice into SLF with words on nodes, like `ps_lattice_write_htk`.
+    ///
+    /// Link posteriors are meaningful only after `posterior` is called.
+    pub fn to_slf(&self) -> SlfLattice {
+        let lmath = self.decoder.logmath();
+        let frate = self.decoder.frame_rate() as f64;
+
+        let mut indices = HashMap::new();
+        let mut nodes = Vec::new();
+        for node in self.nodes() {
+            indices.insert(node.raw, nodes.len());
+            let word = node.word();
+            let (word, variant) = split_variant(&word);
+            nodes.push(SlfNode {
+                time: Some(node.frames().0 as f64 / frate),
+                word: Some(word.to_owned()),
+                variant: Some(variant),
+            });
+        }
+
+        let mut links = Vec::new();
+        for node in self.nodes() {
+            for link in node.exits() {
+                let end = match indices.get(&link.nodes().1.raw) {
+                    Some(&end) => end,
+                    None => continue,
+                };
+                links.push(SlfLink {
+                    start: indices[&node.raw],
+                    end,
+                    word: None,
+                    acoustic: Some(lmath.log_to_ln(link.acoustic_score())),
+                    language: None,
+                    posterior: Some(lmath.confidence(link.posterior())),
+                });
+            }
+        }
+
+        SlfLattice {
+            header: vec![("VERSION".to_owned(), "1.0".to_owned())],
+            nodes,
+            links,
+        }
+    }
+
+    pub fn write_htk<W: Write>(&self, writer: W) -> Result<()> {
+        self.to_slf().write_htk(writer)
+    }
+
+    pub fn write_dot<W: Write>(&self, writer: W) -> Result<()> {
+        self.to_slf().write_dot(writer)
+    }
+}
+
+// Splits dictionary word like `read(2)` into base word and pronunciation variant.
+pub(crate) fn split_variant(word: &str) -> (&str, u32) {
+    if word.ends_with(')') {
+        if let Some(open) = word.rfind('(') {
+            if let Ok(variant) = word[open + 1..word.len() - 1].parse() {
+                return (&word[..open], variant);
+            }
+        }
+    }
+    (word, 1)
+}
+
+fn quote(value: &str) -> String {
+    if !value.is_empty() && !value.contains(|c: char| c.is_whitespace() || c == '"' || c == '\\') {
+        return value.to_owned();
+    }
+    let mut quoted = String::from("\"");
+    for ch in value.chars() {
+        if ch == '"' || ch == '\\' {
+            quoted.push('\\');
+        }
+        quoted.push(ch);
+    }
+    quoted.push('"');
+    quoted
+}
+
+// Splits line into `name=value` fields, values may be quoted.
+fn fields(line: &str) -> Option<Vec<(String, String)>> {
+    let mut fields = Vec::new();
+    let mut chars = line.chars().peekable();
+    loop {
+        while chars.peek().is_some_and(|c| c.is_whitespace()) {
+            chars.next();
+        }
+        if chars.peek().is_none() {
+            return Some(fields);
+        }
+        let mut name = String::new();
+        loop {
+            match chars.next() {
+                Some('=') => break,
+                Some(ch) if !ch.is_whitespace() => name.push(ch),
+                _ => return None,
+            }
+        }
+        let mut value = String::new();
+        if chars.peek() == Some(&'"') {
+            chars.next();
+            loop {
+                match chars.next() {
+                    Some('"') => break,
+                    Some('\\') => value.push(chars.next()?),
+                    Some(ch) => value.push(ch),
+                    None => return None,
+                }
+            }
+        } else {
+            while let Some(&ch) = chars.peek() {
+                if ch.is_whitespace() {
+                    break;
+                }
+                value.push(ch);
+                chars.next();
+            }
+        }
+        fields.push((name, value));
+    }
+}
+
+fn lattice_error(line: usize, message: String) -> Error {
+    Error::Lattice { line: Some(line), message }
+}
+
+// Orders definitions by index, each of `count` indices must be defined once.
+fn in_order<T>(mut items: Vec<(usize, T)>, count: usize, what: &str) -> Result<Vec<T>> {
+    items.sort_by_key(|&(index, _)| index);
+    for (expected, &(index, _)) in items.iter().enumerate() {
+        if index != expected {
+            let (index, problem) = if index < expected {
+                (index, "defined twice")
+            } else {
+                (expected, "not defined")
+            };
+            return Err(Error::Lattice { line: None, message: format!("{} {} is {}", what, index, problem) });
+        }
+    }
+    if items.len() < count {
+        return Err(Error::Lattice { line: None, message: format!("{} {} is not defined", what, items.len()) });
+    }
+    Ok(items.into_iter().map(|(_, item)| item).collect())
+}
+
+impl SlfLattice {
+    pub fn from_file<P: AsRef<Path>>(path: P) -> Result<Self> {
+        SlfLattice::read(BufReader::new(File::open(path)?))
+    }
+
+    pub fn read<R: BufRead>(reader: R) -> Result<Self> {
+        let mut header = Vec::new();
+        // Counts come from file, so definitions are collected as they go
+        // instead of allocating declared number of them up front.
+        let mut counts = None;
+        let mut nodes = Vec::new();
+        let mut links = Vec::new();
+        for (number, line) in reader.lines().enumerate() {
+            let line = line?;
+            let number = number + 1;
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let fields = fields(line)
+                .ok_or_else(|| lattice_error(number, "malformed line".to_owned()))?;
+            let parse_float = |value: &str| value.parse::<f64>()
+                .map_err(|_| lattice_error(number, format!("invalid number '{}'", value)));
+            let parse_index = |value: &str| value.parse::<usize>()
+                .map_err(|_| lattice_error(number, format!("invalid index '{}'", value)));
+
+            match fields[0].0.as_str() {
+                "N" | "NODES" => {
+                    let mut n_nodes = parse_index(&fields[0].1)?;
+                    let mut n_links = 0;
+                    for (name, value) in &fields[1..] {
+                        match name.as_str() {
+                            "L" | "LINKS" => n_links = parse_index(value)?,
+                            "N" | "NODES" => n_nodes = parse_index(value)?,
+                            _ => {}
+                        }
+                    }
+                    counts = Some((n_nodes, n_links));
+                }
+                "I" => {
+                    let (n_nodes, _) = counts
+                        .ok_or_else(|| lattice_error(number, "node goes before N= line".to_owned()))?;
+                    let index = parse_index(&fields[0].1)?;
+                    if index >= n_nodes {
+                        return Err(lattice_error(number, format!("node {} is out of range", index)));
+                    }
+                    let mut node = SlfNode::default();
+                    for (name, value) in &fields[1..] {
+                        match name.as_str() {
+                            "t" | "time" => node.time = Some(parse_float(value)?),
+                            "W" | "WORD" => node.word = Some(value.clone()),
+                            "v" | "var" => node.variant = Some(parse_index(value)? as u32),
+                            _ => {}
+                        }
+                    }
+                    nodes.push((index, node));
+                }
+                "J" => {
+                    let (n_nodes, n_links) = counts
+                        .ok_or_else(|| lattice_error(number, "link goes before L= line".to_owned()))?;
+                    let index = parse_index(&fields[0].1)?;
+                    if index >= n_links {
+                        return Err(lattice_error(number, format!("link {} is out of range", index)));
+                    }
+                    let mut link = SlfLink::default();
+                    for (name, value) in &fields[1..] {
+                        match name.as_str() {
+                            "S" | "START" => link.start = parse_index(value)?,
+                            "E" | "END" => link.end = parse_index(value)?,
+                            "W" | "WORD" => link.word = Some(value.clone()),
+                            "a" | "acoustic" => link.acoustic = Some(parse_float(value)?),
+                            "l" | "language" => link.language = Some(parse_float(value)?),
+                            "p" | "posterior" => link.posterior = Some(parse_float(value)?),
+                            _ => {}
+                        }
+                    }
+                    if link.start >= n_nodes || link.end >= n_nodes {
+                        return Err(lattice_error(number, format!("link {} refers to missing node", index)));
+                    }
+                    links.push((index, link));
+                }
+                _ => {
+                    if counts.is_some() {
+                        return Err(lattice_error(number, "header field after node definitions".to_owned()));
+                    }
+                    header.extend(fields);
+                }
+            }
+        }
+        let (n_nodes, n_links) = counts
+            .ok_or_else(|| Error::Lattice { line: None, message: "no N= line".to_owned() })?;
+        Ok(SlfLattice {
+            header,
+            nodes: in_order(nodes, n_nodes, "node")?,
+            links: in_order(links, n_links, "link")?,
+        })
+    }
+
+    pub fn write_htk<W: Write>(&self, mut writer: W) -> Result<()> {
+        if !self.header.iter().any(|(name, _)| name == "VERSION") {
+            writeln!(writer, "VERSION=1.0")?;
+        }
+        for (name, value) in &self.header {
+            writeln!(writer, "{}={}", name, quote(value))?;
+        }
+        writeln!(writer, "N={}\tL={}", self.nodes.len(), self.links.len())?;
+        for (index, node) in self.nodes.iter().enumerate() {
+            write!(writer, "I={}", index)?;
+            if let Some(time) = node.time {
+                write!(writer, "\tt={:.2}", time)?;
+            }
+            if let Some(ref word) = node.word {
+                write!(writer, "\tW={}", quote(word))?;
+            }
+            if let Some(variant) = node.variant {
+                write!(writer, "\tv={}", variant)?;
+            }
+            writeln!(writer)?;
+        }
+        for (index, link) in self.links.iter().enumerate() {
+            write!(writer, "J={}\tS={}\tE={}", index, link.start, link.end)?;
+            if let Some(ref word) = link.word {
+                write!(writer, "\tW={}", quote(word))?;
+            }
+            if let Some(acoustic) = link.acoustic {
+                write!(writer, "\ta={}", acoustic)?;
+            }
+            if let Some(language) = link.language {
+                write!(writer, "\tl={}", language)?;
+            }
+            if let Some(posterior) = link.posterior {
+                write!(writer, "\tp={}", posterior)?;
+            }
+            writeln!(writer)?;
+        }
+        Ok(())
+    }
+
+    /// Writes lattice as Graphviz digraph, links are labeled with posteriors
+    /// when they are known.
+    pub fn write_dot<W: Write>(&self, mut writer: W) -> Result<()> {
+        let escape = |label: &str| label.replace('\\', "\\\\").replace('"', "\\\"");
+        writeln!(writer, "digraph lattice {{")?;
+        writeln!(writer, "  rankdir=LR;")?;
+        for (index, node) in self.nodes.iter().enumerate() {
+            let mut label = escape(node.word.as_ref().map_or("!NULL", |word| word.as_str()));
+            if let Some(time) = node.time {
+                label.push_str(&format!("\\n{:.2}", time));
+            }
+            writeln!(writer, "  n{} [label=\"{}\"];", index, label)?;
+        }
+        for link in &self.links {
+            let mut label = link.word.as_ref().map(|word| escape(word)).unwrap_or_default();
+            if let Some(posterior) = link.posterior {
+                if !label.is_empty() {
+                    label.push_str("\\n");
+                }
+                label.push_str(&format!("{:.3}", posterior));
+            }
+            writeln!(writer, "  n{} -> n{} [label=\"{}\"];", link.start, link.end, label)?;
+        }
+        writeln!(writer, "}}")?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const LATTICE: &str = "\
+# Lattice
+VERSION=1.0
+UTTERANCE=\"utt 1\"
+N=3\tL=2
+I=0\tt=0.00\tW=<s>\tv=1
+I=2\tt=0.50\tW=</s>
+I=1\tt=0.10\tW=\"say \\\"hi\\\"\"
+J=0\tS=0\tE=1\ta=-10.5\tp=0.9
+J=1\tS=1\tE=2\ta=-3\tl=-1.5
+";
+
+    fn error_line(result: Result<SlfLattice>) -> Option<usize> {
+        match result {
+            Err(Error::Lattice { line, .. }) => line,
+            other => panic!("unexpected result {:?}", other),
+        }
+    }
+
+    #[test]
+    fn read() {
+        let lattice = SlfLattice::read(LATTICE.as_bytes()).unwrap();
+        assert_eq!(lattice.header, vec![
+            ("VERSION".to_owned(), "1.0".to_owned()),
+            ("UTTERANCE".to_owned(), "utt 1".to_owned()),
+        ]);
+        assert_eq!(lattice.nodes.len(), 3);
+        assert_eq!(lattice.nodes[1].word.as_ref().unwrap(), "say \"hi\"");
+        assert_eq!(lattice.nodes[2].time, Some(0.5));
+        assert_eq!(lattice.nodes[0].variant, Some(1));
+        assert_eq!(lattice.links[1], SlfLink {
+            start: 1,
+            end: 2,
+            word: None,
+            acoustic: Some(-3.0),
+            language: Some(-1.5),
+            posterior: None,
+        });
+    }
+
+    #[test]
+    fn write_and_read_back() {
+        let lattice = SlfLattice::read(LATTICE.as_bytes()).unwrap();
+        let mut written = Vec::new();
+        lattice.write_htk(&mut written).unwrap();
+        assert_eq!(SlfLattice::read(&written[..]).unwrap(), lattice);
+    }
+
+    #[test]
+    fn write_dot() {
+        let lattice = SlfLattice::read(LATTICE.as_bytes()).unwrap();
+        let mut written = Vec::new();
+        lattice.write_dot(&mut written).unwrap();
+        let dot = String::from_utf8(written).unwrap();
+        assert!(dot.starts_with("digraph lattice {"));
+        assert!(dot.contains("n1 [label=\"say \\\"hi\\\"\\n0.10\"];"));
+        assert!(dot.contains("n0 -> n1 [label=\"0.900\"];"));
+    }
+
+    #[test]
+    fn errors_point_to_line() {
+        assert_eq!(error_line(SlfLattice::read("N=2 L=1\nI=0\nI=1\nJ=0 S=0 E=2\n".as_bytes())), Some(4));
+        assert_eq!(error_line(SlfLattice::read("N=1 L=0\nI=1\n".as_bytes())), Some(2));
+        assert_eq!(error_line(SlfLattice::read("I=0\n".as_bytes())), Some(1));
+        assert_eq!(error_line(SlfLattice::read("N=1 L=0\nI=0 t=x\n".as_bytes())), Some(2));
+    }
+
+    #[test]
+    fn missing_and_duplicate_definitions() {
+        assert_eq!(error_line(SlfLattice::read("N=2 L=0\nI=0\n".as_bytes())), None);
+        assert_eq!(error_line(SlfLattice::read("N=2 L=0\nI=0\nI=0\n".as_bytes())), None);
+        assert_eq!(error_line(SlfLattice::read("VERSION=1.0\n".as_bytes())), None);
+    }
+
+    #[test]
+    fn huge_counts_are_not_allocated() {
+        let result = SlfLattice::read("N=4000000000 L=4000000000\nI=3999999999\n".as_bytes());
+        assert_eq!(error_line(result), None);
+    }
+
+    #[test]
+    fn variants_are_split() {
+        assert_eq!(split_variant("read(2)"), ("read", 2));
+        assert_eq!(split_variant("read"), ("read", 1));
+        assert_eq!(split_variant("(x)"), ("(x)", 1));
+    }
+}
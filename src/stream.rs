@@ -0,0 +1,101 @@
+// Adapter between async audio streams and blocking decoder.
+//
+// Decoding runs on dedicated thread which pulls audio stream itself and
+// pushes updates into bounded channel. When consumer is slow, thread blocks
+// on sending and stops pulling audio, so backpressure propagates to audio
+// source. Dropping `HypothesisStream` cancels decoding.
+
+use std::pin::Pin;
+use std::task::{Context, Poll};
+use std::thread;
+
+use futures::channel::{mpsc, oneshot};
+use futures::executor::block_on;
+use futures::future::{self, Either};
+use futures::{Future, FutureExt, SinkExt, Stream, StreamExt};
+
+use super::{HypothesisUpdate, PsDecoder, Result};
+
+// Number of updates which may wait for consumer before decoding is paused.
+const UPDATES_BUFFER: usize = 4;
+
+/// Stream of interim updates ending with final one, see `PsDecoder::decode_stream`.
+pub struct HypothesisStream {
+    updates: mpsc::Receiver<Result<HypothesisUpdate>>,
+    decoder: oneshot::Receiver<PsDecoder>,
+    // Dropped together with stream, which tells decoding thread to stop.
+    _cancel: oneshot::Sender<()>,
+}
+
+impl HypothesisStream {
+    /// Stops decoding, if it isn't finished yet, and returns decoder once
+    /// decoding thread releases it.
+    pub fn into_decoder(self) -> impl Future<Output = Option<PsDecoder>> {
+        let HypothesisStream { decoder, .. } = self;
+        decoder.map(|decoder| decoder.ok())
+    }
+}
+
+impl Stream for HypothesisStream {
+    type Item = Result<HypothesisUpdate>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        self.updates.poll_next_unpin(cx)
+    }
+}
+
+impl PsDecoder {
+    /// Decodes audio stream as single utterance on separate thread.
+    ///
+    /// Stream yields interim updates when hypothesis changes and final one
+    /// when audio is over, it ends after first error.
+    pub fn decode_stream<S>(self, audio: S) -> Result<HypothesisStream>
+        where S: Stream<Item = Vec<i16>> + Send + 'static
+    {
+        let (updates_tx, updates_rx) = mpsc::channel(UPDATES_BUFFER);
+        let (decoder_tx, decoder_rx) = oneshot::channel();
+        let (cancel_tx, cancel_rx) = oneshot::channel();
+        thread::Builder::new()
+            .name("pocketsphinx-stream".to_owned())
+            .spawn(move || {
+                let mut decoder = self;
+                decode(&mut decoder, Box::pin(audio), updates_tx, cancel_rx);
+                let _ = decoder_tx.send(decoder);
+            })?;
+        Ok(HypothesisStream { updates: updates_rx, decoder: decoder_rx, _cancel: cancel_tx })
+    }
+}
+
+fn decode<S>(decoder: &mut PsDecoder,
+             mut audio: Pin<Box<S>>,
+             mut updates: mpsc::Sender<Result<HypothesisUpdate>>,
+             mut cancel: oneshot::Receiver<()>)
+    where S: Stream<Item = Vec<i16>>
+{
+    let mut utterance = match decoder.utterance(None) {
+        Ok(utterance) => utterance,
+        Err(err) => {
+            let _ = block_on(updates.send(Err(err)));
+            return;
+        }
+    };
+    loop {
+        let block = match block_on(future::select(audio.next(), &mut cancel)) {
+            Either::Left((Some(block), _)) => block,
+            Either::Left((None, _)) => break,
+            // Utterance is ended when dropped.
+            Either::Right(_) => return,
+        };
+        let update = match utterance.process_partial(&block) {
+            Ok(Some(update)) => Ok(update),
+            Ok(None) => continue,
+            Err(err) => Err(err),
+        };
+        let failed = update.is_err();
+        // Sending fails when stream is dropped.
+        if block_on(updates.send(update)).is_err() || failed {
+            return;
+        }
+    }
+    let _ = block_on(updates.send(utterance.finish_update()));
+}
@@ -0,0 +1,109 @@
+use super::{Hypothesis, HypothesisUpdate, PsDecoder, Resampler, Result};
+
+/// Utterance being decoded, ends when finished or dropped.
+///
+/// Holds decoder borrowed mutably, so that utterances can't overlap.
+pub struct Utterance<'a> {
+    decoder: &'a mut PsDecoder,
+    finished: bool,
+    resampler: Option<Resampler>,
+    // Text of last reported update.
+    reported: String,
+}
+
+impl PsDecoder {
+    pub fn utterance(&mut self, utt_id: Option<&str>) -> Result<Utterance<'_>> {
+        self.start_utt(utt_id)?;
+        Ok(Utterance {
+            decoder: self,
+            finished: false,
+            resampler: None,
+            reported: String::new(),
+        })
+    }
+}
+
+impl<'a> Utterance<'a> {
+    /// Processes audio samples, returns number of frames searched.
+    pub fn process(&mut self, data: &[i16]) -> Result<i32> {
+        match self.resampler {
+            Some(ref mut resampler) => {
+                let mut resampled = Vec::with_capacity(data.len());
+                resampler.process(data, &mut resampled);
+                self.decoder.process_raw(&resampled, false, false)
+            }
+            None => self.decoder.process_raw(data, false, false),
+        }
+    }
+
+    /// Declares sample rate of audio passed to `process`, it is converted
+    /// to decoder's `-samprate` when they differ.
+    pub fn set_input_rate(&mut self, rate: u32) -> Result<()> {
+        self.flush_resampler()?;
+        let model_rate = self.decoder.sample_rate();
+        self.resampler = if rate == model_rate {
+            None
+        } else {
+            Some(Resampler::new(rate, model_rate)?)
+        };
+        Ok(())
+    }
+
+    fn flush_resampler(&mut self) -> Result<()> {
+        if let Some(ref mut resampler) = self.resampler {
+            let mut rest = Vec::new();
+            resampler.flush(&mut rest);
+            self.decoder.process_raw(&rest, false, false)?;
+        }
+        Ok(())
+    }
+
+    pub fn in_speech(&self) -> bool {
+        self.decoder.get_in_speech()
+    }
+
+    /// Returns hypothesis for audio processed so far.
+    pub fn hyp_partial(&self) -> Option<Hypothesis> {
+        self.decoder.get_hyp()
+    }
+
+    /// Returns interim result if hypothesis text has changed since last
+    /// reported one.
+    pub fn partial_update(&mut self) -> Option<HypothesisUpdate> {
+        let text = self.decoder.get_hyp().map(|hyp| hyp.text).unwrap_or_default();
+        if text == self.reported {
+            return None;
+        }
+        let update = HypothesisUpdate::interim(&self.reported, text.clone());
+        self.reported = text;
+        Some(update)
+    }
+
+    /// Processes audio samples and returns interim result if it has changed.
+    pub fn process_partial(&mut self, data: &[i16]) -> Result<Option<HypothesisUpdate>> {
+        self.process(data)?;
+        Ok(self.partial_update())
+    }
+
+    /// Finishes utterance, returns final result even if it is the same as
+    /// last interim one.
+    pub fn finish_update(self) -> Result<HypothesisUpdate> {
+        let text = self.finish()?.map(|hyp| hyp.text).unwrap_or_default();
+        Ok(HypothesisUpdate::last(text))
+    }
+
+    pub fn finish(mut self) -> Result<Option<Hypothesis>> {
+        self.flush_resampler()?;
+        self.finished = true;
+        self.decoder.end_utt()?;
+        Ok(self.decoder.get_hyp())
+    }
+}
+
+impl<'a> Drop for Utterance<'a> {
+    fn drop(&mut self) {
+        if !self.finished {
+            let _ = self.decoder.end_utt();
+        }
+    }
+}